@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+
+use serde_derive::{Deserialize, Serialize};
+
+use super::{default, Config};
+
+/// Behaviour of buddy, described as a state machine.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Behaviour {
+    /// State buddy starts in.
+    pub(crate) initial: String,
+    pub(crate) states: BTreeMap<String, StateDefinition>,
+}
+
+/// State that buddy can be in.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct StateDefinition {
+    /// Animation played while in this state.
+    pub(crate) animation: String,
+    /// Whether buddy moves along the screen while in this state.
+    #[serde(default)]
+    pub(crate) moving: bool,
+    /// How buddy moves, if it does.
+    #[serde(default)]
+    pub(crate) movement: Movement,
+    /// Animation played while moving mostly upwards. Falls back to the `up` animation of the sprite pack, if any, or the animation of the state.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) up: Option<String>,
+    /// Animation played while moving mostly downwards. Falls back to the `down` animation of the sprite pack, if any, or the animation of the state.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) down: Option<String>,
+    /// Whether buddy turns around when entering this state.
+    #[serde(default)]
+    pub(crate) turn: bool,
+    /// Transitions to other states, checked in order.
+    #[serde(default)]
+    pub(crate) transitions: Vec<Transition>,
+}
+
+/// Way of moving across the screen.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Movement {
+    /// Walk along the bottom of the screen.
+    #[default]
+    Walk,
+    /// Walk (or fly) straight to random points on the screen.
+    Wander,
+    /// Walk to the side of the screen and climb up along it.
+    Climb,
+    /// Walk along the bottom of the screen towards the pointer.
+    Follow,
+    /// Walk (or fly) straight towards the pointer.
+    Chase,
+    /// Run away from the pointer along the bottom of the screen.
+    Flee,
+}
+
+impl Movement {
+    /// Whether buddy stays on the ground while moving this way.
+    pub(crate) fn grounded(self) -> bool {
+        matches!(self, Movement::Walk | Movement::Follow | Movement::Flee)
+    }
+}
+
+/// Change from one state to another.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Transition {
+    pub(crate) trigger: Trigger,
+    pub(crate) target: Target,
+    /// Chance (in percent) of the transition being taken when triggered.
+    #[serde(default = "certain")]
+    pub(crate) chance: u8,
+    /// Milliseconds after entering the state until a timer trigger fires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) after: Option<u64>,
+    /// If set, timer triggers fire after a random duration between `after` and `until` milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) until: Option<u64>,
+}
+
+/// State(s) a transition changes to.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Target {
+    /// Name of the state to change to.
+    State(String),
+    /// Names of states to pick from at random, with their weights.
+    Weighted(BTreeMap<String, u32>),
+}
+
+impl Target {
+    /// Names of all states the transition may change to.
+    pub(crate) fn states(&self) -> Vec<&String> {
+        match self {
+            Target::State(state) => vec![state],
+            Target::Weighted(states) => states.keys().collect(),
+        }
+    }
+}
+
+/// Event that may cause a transition.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Trigger {
+    /// Buddy is clicked.
+    Click,
+    /// Buddy has been in the state for `after` (up to `until`) milliseconds.
+    Timer,
+    /// Fires once per second, usually combined with a chance.
+    Random,
+    /// Buddy reaches the edge of the screen, or the top while climbing.
+    Edge,
+    /// Buddy reaches the point it is wandering to, or the pointer it is following.
+    Arrive,
+    /// The pointer is farther away from buddy than the pointer radius. Only the horizontal distance counts if the transition changes to states on the ground. Checked ten times per second.
+    PointerAway,
+    /// The pointer is within the pointer radius around buddy. Checked ten times per second.
+    PointerNear,
+    /// The animation of the state has finished. Never fires for looping animations.
+    AnimationEnd,
+}
+
+fn certain() -> u8 {
+    100
+}
+
+impl Behaviour {
+    /// Default behaviour: clicking toggles between idling and running. Clicking an idle buddy plays the click animation with a chance of `onclick_event_chance` percent.
+    ///
+    /// If autonomous, buddy also decides on its own to walk, stop, turn around or keep idling whenever it has spent a random duration in a state.
+    ///
+    /// Following the cursor, idle buddy walks towards the pointer along the bottom of the screen whenever it gets away and idles again once it arrives.
+    ///
+    /// Fleeing from the cursor, buddy runs away from the pointer whenever it comes close, playing the scared animation if the sprite pack provides one. Takes precedence over following.
+    pub(crate) fn new(config: &Config) -> Self {
+        let state = |animation: &str, moving: bool, turn: bool, transitions: Vec<Transition>| {
+            StateDefinition {
+                animation: animation.to_string(),
+                moving,
+                movement: Movement::Walk,
+                up: None,
+                down: None,
+                turn,
+                transitions,
+            }
+        };
+        let transition = |trigger: Trigger, target: &str, chance: u8| Transition {
+            trigger,
+            target: Target::State(target.to_string()),
+            chance,
+            after: None,
+            until: None,
+        };
+
+        let mut idle = vec![
+            transition(Trigger::Click, "click", config.onclick_event_chance),
+            transition(Trigger::Click, "run", 100),
+        ];
+        let mut run = vec![transition(Trigger::Click, "idle", 100)];
+
+        let mut states = BTreeMap::new();
+
+        if config.flee_cursor {
+            idle.push(transition(Trigger::PointerNear, "flee", 100));
+            run.push(transition(Trigger::PointerNear, "flee", 100));
+
+            let mut flee = state(
+                "scared",
+                true,
+                false,
+                vec![transition(Trigger::PointerAway, "idle", 100)],
+            );
+            flee.movement = Movement::Flee;
+            states.insert("flee".to_string(), flee);
+        } else if config.follow_cursor {
+            idle.push(transition(Trigger::PointerAway, "follow", 100));
+
+            let mut follow = state(
+                "run",
+                true,
+                false,
+                vec![
+                    transition(Trigger::Arrive, "idle", 100),
+                    transition(Trigger::Click, "idle", 100),
+                ],
+            );
+            follow.movement = Movement::Follow;
+            states.insert("follow".to_string(), follow);
+        }
+
+        if config.autonomous {
+            let autonomy = &config.autonomy;
+            let decision = Transition {
+                trigger: Trigger::Timer,
+                target: Target::Weighted(BTreeMap::from([
+                    ("run".to_string(), autonomy.walk_weight),
+                    ("idle".to_string(), autonomy.idle_weight),
+                    ("turn".to_string(), autonomy.turn_weight),
+                ])),
+                chance: 100,
+                after: Some(autonomy.min_duration),
+                until: Some(autonomy.max_duration),
+            };
+
+            idle.push(decision.clone());
+            run.push(decision);
+        }
+
+        states.extend([
+            ("idle".to_string(), state("idle", false, false, idle)),
+            ("run".to_string(), state("run", true, false, run.clone())),
+            // walking into the opposite direction
+            ("turn".to_string(), state("run", true, true, run)),
+            (
+                "click".to_string(),
+                state(
+                    "click",
+                    false,
+                    false,
+                    vec![transition(Trigger::AnimationEnd, "idle", 100)],
+                ),
+            ),
+        ]);
+
+        Behaviour {
+            initial: "idle".to_string(),
+            states,
+        }
+    }
+}
+
+/// Settings of autonomous behaviour.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Autonomy {
+    /// Weight of deciding to walk.
+    pub(crate) walk_weight: u32,
+    /// Weight of deciding to idle (or stop walking).
+    pub(crate) idle_weight: u32,
+    /// Weight of deciding to turn around and walk.
+    pub(crate) turn_weight: u32,
+    /// Minimum time in milliseconds before the next decision.
+    pub(crate) min_duration: u64,
+    /// Maximum time in milliseconds before the next decision.
+    pub(crate) max_duration: u64,
+}
+
+impl Default for Autonomy {
+    fn default() -> Self {
+        Autonomy {
+            walk_weight: default::WALK_WEIGHT,
+            idle_weight: default::IDLE_WEIGHT,
+            turn_weight: default::TURN_WEIGHT,
+            min_duration: default::MIN_DURATION,
+            max_duration: default::MAX_DURATION,
+        }
+    }
+}
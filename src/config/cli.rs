@@ -1,28 +1,61 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use clap_num::number_range;
 
+use super::{EdgePolicy, ScalingFilter};
+use crate::control::Command;
+
 #[derive(Parser, Debug)]
 #[command(name = "Buddy")]
 #[command(author = "Hannah F. <github: Hqnnqh>")]
 #[command(version = "1.0")]
 #[command(about = r#"Your new best buddy when using your computer :)!"#, long_about = None)]
+#[command(
+    after_help = "Signals: SIGUSR1 reloads the sprites, SIGUSR2 pauses or resumes buddy and SIGHUP reloads the configuration by default (see [signals] in the config file). SIGTERM and SIGINT close buddy."
+)]
 pub(crate) struct Cli {
+    #[command(subcommand)]
+    pub(crate) command: Option<CliCommand>,
+
     #[clap(
         short = 's',
         long,
         value_name = "PATH",
-        help = "Initial path to directory with animation sprites. Directory must contain subdirectories for each event type."
+        help = "Initial path to directory with animation sprites. Directory must contain subdirectories for each event type or a sprite sheet descriptor. Built-in sprite packs can be selected using builtin:<NAME>."
     )]
     pub(crate) sprites_path: Option<String>,
 
+    #[clap(
+        short,
+        long,
+        value_name = "NAME",
+        conflicts_with = "sprites_path",
+        help = "Name of built-in sprite pack to use: chicken, green_slime or pikachu."
+    )]
+    pub(crate) pack: Option<String>,
+
     #[clap(
         short,
         long,
         value_name = "SIZE",
-        help = "Size of character in pixels (should match animation sprites)."
+        help = "Size of character in pixels. Sprites are scaled to fit this size."
     )]
     pub(crate) character_size: Option<u16>,
 
+    #[clap(
+        long,
+        value_name = "FILTER",
+        help = "Interpolation used to scale sprites to the character size."
+    )]
+    pub(crate) scaling_filter: Option<ScalingFilter>,
+
+    #[clap(
+        short = 'I',
+        long,
+        value_name = "INTEGER-SCALING",
+        help = "Only scale sprites by whole-numbered factors (or their inverse) to keep pixel art sharp."
+    )]
+    pub(crate) integer_scaling: Option<bool>,
+
     #[clap(
         short,
         long,
@@ -32,17 +65,47 @@ pub(crate) struct Cli {
     pub(crate) fps: Option<u32>,
 
     #[clap(
-        short,
+        long,
+        value_name = "PIXELS",
+        help = "Pixels per second the character moves with."
+    )]
+    pub(crate) speed: Option<u32>,
+
+    #[clap(
+        long,
+        value_name = "PIXELS",
+        help = "Pixels the character moves at once. Larger steps make for choppier, more pixel art like movement."
+    )]
+    pub(crate) movement_step: Option<u32>,
+
+    #[clap(
+        short = 'm',
         long,
         value_name = "AMOUNT",
-        help = "How often the character's position is updated per second."
+        help = "Deprecated, use --speed: how often the character's position is updated per second."
     )]
     pub(crate) movement_speed: Option<u32>,
+
+    #[clap(
+        long,
+        value_name = "POLICY",
+        help = "What buddy does when walking into the edge of the screen."
+    )]
+    pub(crate) edge: Option<EdgePolicy>,
+
+    #[clap(
+        short = 'w',
+        long,
+        value_name = "WALK-ON-WINDOWS",
+        help = "Let buddy stand and walk on top of windows (Hyprland and Sway only)."
+    )]
+    pub(crate) walk_on_windows: Option<bool>,
+
     #[clap(
         short = 'S',
         long,
         value_name = "AMOUNT",
-        help = "How often to check for signals per second. Or how often to reload sprites if automatic reload is enabled."
+        help = "How often to reload sprites per second if automatic reload is enabled."
     )]
     pub(crate) signal_frequency: Option<u32>,
 
@@ -63,6 +126,36 @@ pub(crate) struct Cli {
        )]
     pub(crate) onclick_event_chance: Option<u8>,
 
+    #[clap(
+        short,
+        long,
+        value_name = "AUTONOMOUS",
+        help = "Let buddy decide on its own when to walk, stop and turn around."
+    )]
+    pub(crate) autonomous: Option<bool>,
+
+    #[clap(
+        short = 'F',
+        long,
+        value_name = "FOLLOW-CURSOR",
+        help = "Let buddy follow the mouse pointer along the bottom of the screen."
+    )]
+    pub(crate) follow_cursor: Option<bool>,
+
+    #[clap(
+        long,
+        value_name = "FLEE-CURSOR",
+        help = "Let buddy run away from the mouse pointer when it comes close. Takes precedence over following it."
+    )]
+    pub(crate) flee_cursor: Option<bool>,
+
+    #[clap(
+        long,
+        value_name = "PIXELS",
+        help = "Distance from buddy within which the mouse pointer counts as close."
+    )]
+    pub(crate) pointer_radius: Option<u32>,
+
     #[clap(
         short,
         long,
@@ -103,6 +196,22 @@ pub(crate) struct Cli {
     )]
     pub(crate) flip_vertical: Option<bool>,
 
+    #[clap(
+        short = 'M',
+        long,
+        value_name = "AUTO-MIRROR",
+        help = "Mirror sprites to face the direction buddy moves into."
+    )]
+    pub(crate) auto_mirror: Option<bool>,
+
+    #[clap(
+        short = 'R',
+        long,
+        value_name = "RESTORE-STATE",
+        help = "Start where buddy was when it was last closed."
+    )]
+    pub(crate) restore_state: Option<bool>,
+
     #[clap(
         short,
         long,
@@ -120,6 +229,36 @@ pub(crate) struct Cli {
     pub(crate) config_path: Option<String>,
 }
 
+#[derive(Subcommand, Debug)]
+pub(crate) enum CliCommand {
+    /// Talk to running buddies: list them or send one of them a command.
+    #[command(
+        override_usage = "buddy ctl list\n       buddy ctl <ID> <COMMAND>",
+        after_help = "<ID> is the process ID or app ID of a running buddy. Run `buddy ctl <ID> help` to see the commands buddy understands."
+    )]
+    Ctl {
+        #[command(subcommand)]
+        target: CtlTarget,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum CtlTarget {
+    /// List running buddies.
+    List,
+    /// Send a command to the buddy with process ID (or app ID) <ID>, e.g. `buddy ctl 4242 play click`.
+    #[command(external_subcommand)]
+    Instance(Vec<String>),
+}
+
+/// Command sent to a running buddy, parsed from the arguments following its ID.
+#[derive(Parser, Debug)]
+#[command(name = "buddy ctl <ID>", bin_name = "buddy ctl <ID>")]
+pub(crate) struct CtlCommand {
+    #[command(subcommand)]
+    pub(crate) command: Command,
+}
+
 fn less_than_101(s: &str) -> Result<u8, String> {
     number_range(s, 0, 100)
 }
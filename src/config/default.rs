@@ -1,6 +1,12 @@
+use super::{EdgePolicy, ScalingFilter, SignalAction};
+
+pub(crate) const SPRITES_PATH: &str = "builtin:chicken";
 pub(crate) const CHARACTER_SIZE: u16 = 75;
 pub(crate) const FPS: u32 = 4;
-pub(crate) const MOVEMENT_SPEED: u32 = 20;
+pub(crate) const SPEED: u32 = 200;
+pub(crate) const MOVEMENT_STEP: u32 = 10;
+/// Pixels buddy moved per update before speed was expressed in pixels per second.
+pub(crate) const LEGACY_STEP: u32 = 10;
 pub(crate) const SIGNAL_FREQUENCY: u32 = 1;
 pub(crate) const AUTOMATIC_RELOAD: bool = false;
 pub(crate) const ON_CLICK_CHANCE: u8 = 15;
@@ -9,4 +15,26 @@ pub(crate) const Y: i32 = 0;
 pub(crate) const RUN_LEFT: bool = false;
 pub(crate) const FLIP_HORIZONTAL: bool = false;
 pub(crate) const FLIP_VERTICAL: bool = false;
+pub(crate) const AUTO_MIRROR: bool = true;
 pub(crate) const DEBUG: bool = false;
+pub(crate) const SCALING_FILTER: ScalingFilter = ScalingFilter::Nearest;
+pub(crate) const INTEGER_SCALING: bool = false;
+pub(crate) const EDGE: EdgePolicy = EdgePolicy::Wrap;
+pub(crate) const AUTONOMOUS: bool = false;
+pub(crate) const WALK_WEIGHT: u32 = 2;
+pub(crate) const IDLE_WEIGHT: u32 = 3;
+pub(crate) const TURN_WEIGHT: u32 = 1;
+pub(crate) const MIN_DURATION: u64 = 2000;
+pub(crate) const MAX_DURATION: u64 = 10000;
+pub(crate) const FOLLOW_CURSOR: bool = false;
+pub(crate) const FLEE_CURSOR: bool = false;
+pub(crate) const POINTER_RADIUS: u32 = 150;
+pub(crate) const WALK_ON_WINDOWS: bool = false;
+pub(crate) const GRAVITY: u32 = 2000;
+pub(crate) const TERMINAL_VELOCITY: u32 = 1500;
+pub(crate) const RESTITUTION: u8 = 30;
+pub(crate) const FRICTION: u32 = 1500;
+pub(crate) const RESTORE_STATE: bool = false;
+pub(crate) const SIGNAL_USR1: SignalAction = SignalAction::ReloadSprites;
+pub(crate) const SIGNAL_USR2: SignalAction = SignalAction::TogglePause;
+pub(crate) const SIGNAL_HUP: SignalAction = SignalAction::ReloadConfig;
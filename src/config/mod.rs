@@ -1,14 +1,29 @@
+use behaviour::{Autonomy, Behaviour};
+use clap::ValueEnum;
 use serde_derive::{Deserialize, Serialize};
+use signal_hook::consts::{SIGHUP, SIGUSR1, SIGUSR2};
 
+pub(crate) mod behaviour;
 pub(crate) mod cli;
 pub(crate) mod default;
 
+/// Prefix of sprites paths that refer to a sprite pack compiled into the binary, e.g. `builtin:chicken`.
+pub(crate) const BUILTIN_PREFIX: &str = "builtin:";
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
+// fields missing in existing config files fall back to their defaults
+#[serde(default)]
 pub(crate) struct Config {
     // can safely be casted as both i32 and u32
     pub(crate) character_size: u16,
     pub(crate) fps: u32,
-    pub(crate) movement_speed: u32,
+    /// Pixels per second buddy moves with.
+    pub(crate) speed: u32,
+    /// Pixels buddy moves at once.
+    pub(crate) movement_step: u32,
+    /// Replaced by `speed`: how often per second buddy moved by ten pixels. Only read to migrate old config files.
+    #[serde(skip_serializing)]
+    pub(crate) movement_speed: Option<u32>,
     pub(crate) onclick_event_chance: u8,
     pub(crate) x: i32,
     pub(crate) y: i32,
@@ -16,9 +31,126 @@ pub(crate) struct Config {
     pub(crate) left: bool,
     pub(crate) flip_horizontal: bool,
     pub(crate) flip_vertical: bool,
+    /// Mirror sprites to face the direction buddy moves into.
+    pub(crate) auto_mirror: bool,
     pub(crate) debug: bool,
     pub(crate) signal_frequency: u32,
     pub(crate) automatic_reload: bool,
+    pub(crate) scaling_filter: ScalingFilter,
+    pub(crate) integer_scaling: bool,
+    pub(crate) edge: EdgePolicy,
+    pub(crate) autonomous: bool,
+    pub(crate) autonomy: Autonomy,
+    /// Let idle buddy follow the pointer.
+    pub(crate) follow_cursor: bool,
+    /// Let buddy run away from the pointer.
+    pub(crate) flee_cursor: bool,
+    /// Distance in pixels from the center of buddy within which the pointer counts as close.
+    pub(crate) pointer_radius: u32,
+    pub(crate) physics: Physics,
+    /// Let buddy stand and walk on top of windows.
+    pub(crate) walk_on_windows: bool,
+    /// Start where buddy was when it was last closed.
+    pub(crate) restore_state: bool,
+    pub(crate) signals: Signals,
+    /// Takes precedence over the behaviour of the sprite pack.
+    pub(crate) behaviour: Option<Behaviour>,
+}
+
+/// Interpolation used when scaling sprites to the character size.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ScalingFilter {
+    /// Keeps pixel art crisp.
+    Nearest,
+    /// Smooths scaled sprites.
+    Bilinear,
+}
+
+/// What buddy does when walking into the edge of the screen.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ValueEnum, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum EdgePolicy {
+    /// Leave the screen and enter it again on the other side.
+    Wrap,
+    /// Turn around.
+    Bounce,
+    /// Stop walking and return to the initial state.
+    Stop,
+    /// Leave the screen and come back some time later at a random position.
+    Respawn,
+}
+
+/// Settings of buddy falling down when above the bottom of the screen or thrown.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Physics {
+    /// Acceleration in pixels per second squared. No falling if 0.
+    pub(crate) gravity: u32,
+    /// Maximum falling speed in pixels per second.
+    pub(crate) terminal_velocity: u32,
+    /// Percentage of speed kept when bouncing off the ground or the edges of the screen.
+    pub(crate) restitution: u8,
+    /// Deceleration in pixels per second squared while sliding along the ground.
+    pub(crate) friction: u32,
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Physics {
+            gravity: default::GRAVITY,
+            terminal_velocity: default::TERMINAL_VELOCITY,
+            restitution: default::RESTITUTION,
+            friction: default::FRICTION,
+        }
+    }
+}
+
+/// What buddy does on receiving a signal.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SignalAction {
+    /// Reload the sprites.
+    ReloadSprites,
+    /// Read the configuration file again and apply it.
+    ReloadConfig,
+    /// Pause buddy, or resume it if paused.
+    TogglePause,
+    /// Play the goodbye animation and close.
+    Quit,
+    /// Do nothing.
+    Ignore,
+}
+
+/// Actions of the signals buddy handles. SIGTERM and SIGINT always close buddy gracefully.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Signals {
+    pub(crate) usr1: SignalAction,
+    pub(crate) usr2: SignalAction,
+    pub(crate) hup: SignalAction,
+}
+
+impl Signals {
+    /// Action configured for `signal`. Signals without a configurable action are ignored.
+    pub(crate) fn action(&self, signal: i32) -> SignalAction {
+        match signal {
+            SIGUSR1 => self.usr1,
+            SIGUSR2 => self.usr2,
+            SIGHUP => self.hup,
+            _ => SignalAction::Ignore,
+        }
+    }
+}
+
+impl Default for Signals {
+    fn default() -> Self {
+        Signals {
+            usr1: default::SIGNAL_USR1,
+            usr2: default::SIGNAL_USR2,
+            hup: default::SIGNAL_HUP,
+        }
+    }
 }
 
 impl Default for Config {
@@ -26,17 +158,33 @@ impl Default for Config {
         Config {
             character_size: default::CHARACTER_SIZE,
             fps: default::FPS,
-            movement_speed: default::MOVEMENT_SPEED,
+            speed: default::SPEED,
+            movement_step: default::MOVEMENT_STEP,
+            movement_speed: None,
             onclick_event_chance: default::ON_CLICK_CHANCE,
             x: default::X,
             y: default::Y,
             left: default::RUN_LEFT,
             flip_horizontal: default::FLIP_HORIZONTAL,
             flip_vertical: default::FLIP_VERTICAL,
+            auto_mirror: default::AUTO_MIRROR,
             debug: default::DEBUG,
             signal_frequency: default::SIGNAL_FREQUENCY,
             automatic_reload: default::AUTOMATIC_RELOAD,
-            sprites_path: None,
+            scaling_filter: default::SCALING_FILTER,
+            integer_scaling: default::INTEGER_SCALING,
+            edge: default::EDGE,
+            autonomous: default::AUTONOMOUS,
+            autonomy: Autonomy::default(),
+            follow_cursor: default::FOLLOW_CURSOR,
+            flee_cursor: default::FLEE_CURSOR,
+            pointer_radius: default::POINTER_RADIUS,
+            physics: Physics::default(),
+            walk_on_windows: default::WALK_ON_WINDOWS,
+            restore_state: default::RESTORE_STATE,
+            signals: Signals::default(),
+            behaviour: None,
+            sprites_path: Some(default::SPRITES_PATH.to_string()),
         }
     }
 }
@@ -0,0 +1,333 @@
+use std::env;
+use std::fs::{self, DirBuilder, Permissions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{ArgAction, Parser, Subcommand};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::config::cli::{CtlCommand, CtlTarget};
+use crate::error::BuddyError;
+
+/// Prefix of the app ID of every buddy, followed by its process ID.
+pub(crate) const APP_ID_PREFIX: &str = "hqnnqh.buddy.instance";
+
+/// How long to wait for a buddy to answer.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Directory holding the control socket of every running buddy of this user, created if missing: `buddy` in the runtime directory or, without one, `buddy-<uid>` in the temporary directory. Fails with [BuddyError] if the directory is not private to this user, as anyone able to write to it could pose as buddy.
+pub(crate) fn socket_dir() -> Result<PathBuf, BuddyError> {
+    // the process directory belongs to the user running buddy
+    let uid = fs::metadata("/proc/self")
+        .map_err(|err| BuddyError::ControlSocketFailed("/proc/self".to_string(), err))?
+        .uid();
+    let dir = match env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime_dir) => PathBuf::from(runtime_dir).join("buddy"),
+        None => env::temp_dir().join(format!("buddy-{}", uid)),
+    };
+    let fail = |err| BuddyError::ControlSocketFailed(dir.display().to_string(), err);
+
+    DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)
+        .map_err(fail)?;
+
+    // don't follow links placed by someone else
+    let metadata = fs::symlink_metadata(&dir).map_err(fail)?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o022 != 0 {
+        return Err(BuddyError::InsecureSocketDir(dir.display().to_string()));
+    }
+    // earlier versions created the directory readable by everyone
+    if metadata.mode() & 0o077 != 0 {
+        fs::set_permissions(&dir, Permissions::from_mode(0o700)).map_err(fail)?;
+    }
+    Ok(dir)
+}
+
+/// Control socket of the buddy running as process `pid`. May fail and return [BuddyError].
+pub(crate) fn socket_path(pid: u32) -> Result<PathBuf, BuddyError> {
+    Ok(socket_dir()?.join(format!("{}.sock", pid)))
+}
+
+/// Command sent to a running buddy, one JSON object per line, e.g. `{"command": "move_to", "x": 100, "y": 0}`. On the command line, e.g. `buddy ctl 4242 move-to 100 0`.
+#[derive(Clone, Debug, Serialize, Deserialize, Subcommand)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub(crate) enum Command {
+    /// Report the status only.
+    Status,
+    /// Play an animation of the sprite pack once, then resume the current state.
+    Play { animation: String },
+    /// Place buddy's bottom left corner at `x`, `y` (from the bottom of the screen).
+    MoveTo { x: i32, y: i32 },
+    /// Change to a state of the behaviour.
+    SetState { state: String },
+    /// Change the speed in pixels per second.
+    SetSpeed { speed: u32 },
+    /// Show `text` in a speech bubble for a few seconds.
+    Say { text: String },
+    /// Freeze buddy in place, or let it carry on.
+    SetPaused {
+        #[arg(action = ArgAction::Set)]
+        paused: bool,
+    },
+    /// Reload the sprites.
+    Reload,
+    /// Close buddy.
+    Quit,
+    /// Keep the connection open to receive every event of buddy, one JSON object per line.
+    Subscribe,
+}
+
+/// Reply to a [Command], one JSON object per line.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Reply {
+    /// Whether the command succeeded.
+    pub(crate) ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+    /// Status of buddy after the command.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) status: Option<Status>,
+}
+
+/// What a running buddy is doing.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Status {
+    pub(crate) pid: u32,
+    /// Current state of the behaviour.
+    pub(crate) state: String,
+    /// Animation being played.
+    pub(crate) animation: String,
+    /// Position of buddy's bottom left corner (from the bottom of the screen).
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    /// Whether buddy faces left.
+    pub(crate) left: bool,
+    /// Pixels per second buddy moves with.
+    pub(crate) speed: u32,
+    pub(crate) paused: bool,
+}
+
+/// Something that happened to a running buddy.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub(crate) enum Event {
+    /// Buddy changed to `state`.
+    State { state: String },
+    /// Buddy has been clicked.
+    Clicked,
+    /// Buddy has been paused or resumed.
+    Paused { paused: bool },
+    /// Buddy has moved to `x`, `y` (from the bottom of the screen). Announced at most ten times per second while moving, and once it comes to rest.
+    Moved { x: i32, y: i32 },
+    /// Something went wrong.
+    Error { message: String },
+}
+
+/// Run `buddy ctl`: list the running buddies or send one of them a command. May fail and return [BuddyError].
+pub(crate) fn run(target: CtlTarget) -> Result<(), BuddyError> {
+    match target {
+        CtlTarget::List => {
+            for pid in instances()? {
+                match send(pid, &Command::Status).map(|(reply, _)| reply.status) {
+                    Ok(Some(status)) => println!(
+                        "{}\t{}{}\t{}\t{}\t{}, {}",
+                        pid, APP_ID_PREFIX, pid, status.state, status.animation, status.x, status.y
+                    ),
+                    _ => println!("{}\t{}{}", pid, APP_ID_PREFIX, pid),
+                }
+            }
+        }
+        CtlTarget::Instance(args) => {
+            let id = args.first().cloned().unwrap_or_default();
+            // prints usage and exits on invalid commands
+            let command = CtlCommand::parse_from(args).command;
+
+            let pid = id
+                .strip_prefix(APP_ID_PREFIX)
+                .unwrap_or(&id)
+                .parse()
+                .map_err(|_| BuddyError::UnknownInstance(id.clone()))?;
+
+            let (reply, stream) = send(pid, &command)?;
+            if !reply.ok {
+                return Err(BuddyError::CommandFailed(reply.error.unwrap_or_default()));
+            }
+
+            match (command, reply.status) {
+                (Command::Status, Some(status)) => {
+                    println!("{}", serde_json::to_string(&status)?)
+                }
+                // print events until buddy quits
+                (Command::Subscribe, _) => {
+                    stream.get_ref().set_read_timeout(None)?;
+                    for event in stream.lines() {
+                        println!("{}", event?);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Process IDs of the running buddies, sorted. Sockets left behind by buddies that are gone are removed. May fail and return [BuddyError].
+fn instances() -> Result<Vec<u32>, BuddyError> {
+    let mut instances: Vec<u32> = fs::read_dir(socket_dir()?)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let pid = path.file_stem()?.to_str()?.parse().ok()?;
+
+            match UnixStream::connect(&path) {
+                Ok(_) => Some(pid),
+                Err(err) => {
+                    if err.kind() == ErrorKind::ConnectionRefused {
+                        let _ = fs::remove_file(&path);
+                    }
+                    None
+                }
+            }
+        })
+        .collect();
+
+    instances.sort();
+    Ok(instances)
+}
+
+/// Send `command` to the buddy running as process `pid` and wait for its reply. Returns the reply and the connection, which carries events after subscribing. May fail and return [BuddyError].
+fn send(pid: u32, command: &Command) -> Result<(Reply, BufReader<UnixStream>), BuddyError> {
+    let path = socket_path(pid)?;
+    if !path.exists() {
+        return Err(BuddyError::UnknownInstance(pid.to_string()));
+    }
+
+    let mut stream = UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let mut message = serde_json::to_vec(command)?;
+    message.push(b'\n');
+    stream.write_all(&message)?;
+    stream.shutdown(Shutdown::Write)?;
+
+    let mut reply = String::new();
+    let mut stream = BufReader::new(stream);
+    stream.read_line(&mut reply)?;
+    Ok((serde_json::from_str(&reply)?, stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// Serialize `value` and read it back, returning both JSON representations.
+    fn round_trip<T>(value: &T) -> (Value, Value)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let line = serde_json::to_string(value).unwrap();
+        let parsed: T = serde_json::from_str(&line).unwrap();
+        (
+            serde_json::from_str(&line).unwrap(),
+            serde_json::to_value(parsed).unwrap(),
+        )
+    }
+
+    #[test]
+    fn commands_survive_round_trip() {
+        for command in [
+            Command::Status,
+            Command::Play {
+                animation: "click".to_string(),
+            },
+            Command::MoveTo { x: 100, y: -5 },
+            Command::SetState {
+                state: "idle".to_string(),
+            },
+            Command::SetSpeed { speed: 300 },
+            Command::Say {
+                text: "Hello \"world\"\n".to_string(),
+            },
+            Command::SetPaused { paused: true },
+            Command::Reload,
+            Command::Quit,
+            Command::Subscribe,
+        ] {
+            let (sent, received) = round_trip(&command);
+            assert_eq!(sent, received);
+        }
+    }
+
+    #[test]
+    fn commands_are_tagged_in_snake_case() {
+        let command: Command =
+            serde_json::from_str(r#"{"command": "move_to", "x": 100, "y": 0}"#).unwrap();
+        assert!(matches!(command, Command::MoveTo { x: 100, y: 0 }));
+
+        assert_eq!(
+            serde_json::to_value(Command::SetPaused { paused: false }).unwrap(),
+            json!({"command": "set_paused", "paused": false})
+        );
+        assert!(serde_json::from_str::<Command>(r#"{"command": "dance"}"#).is_err());
+        assert!(serde_json::from_str::<Command>(r#"{"command": "play"}"#).is_err());
+    }
+
+    #[test]
+    fn commands_parse_from_command_line() {
+        let command = CtlCommand::try_parse_from(["4242", "move-to", "100", "0"])
+            .unwrap()
+            .command;
+        assert!(matches!(command, Command::MoveTo { x: 100, y: 0 }));
+
+        let command = CtlCommand::try_parse_from(["4242", "set-paused", "true"])
+            .unwrap()
+            .command;
+        assert!(matches!(command, Command::SetPaused { paused: true }));
+    }
+
+    #[test]
+    fn replies_survive_round_trip() {
+        let status = Status {
+            pid: 4242,
+            state: "idle".to_string(),
+            animation: "click".to_string(),
+            x: 100,
+            y: 0,
+            left: false,
+            speed: 200,
+            paused: false,
+        };
+
+        for reply in [
+            Reply {
+                ok: true,
+                error: None,
+                status: Some(status),
+            },
+            Reply {
+                ok: false,
+                error: Some("Sprite pack has no animation called: dance".to_string()),
+                status: None,
+            },
+        ] {
+            let (sent, received) = round_trip(&reply);
+            assert_eq!(sent, received);
+        }
+
+        // optional fields are left out
+        let reply: Reply = serde_json::from_str(r#"{"ok":true}"#).unwrap();
+        assert!(reply.ok && reply.error.is_none() && reply.status.is_none());
+        assert_eq!(serde_json::to_value(&reply).unwrap(), json!({"ok": true}));
+    }
+}
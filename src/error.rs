@@ -5,20 +5,58 @@ use thiserror::Error;
 pub(crate) enum BuddyError {
     #[error("Configuration Failed: {0}")]
     InvalidConfig(#[from] ConfyError),
-    #[error("No sprites path specidied")]
-    NoSprites,
     #[error("Graphical Failure: {0}")]
     Glib(#[from] gio::glib::Error),
-    #[error("Signal Subscription Failed: {0}")]
-    SignalSubscriptionFailed(#[from] std::io::Error),
     #[error("Coordinates out of bounds: x: {0}px, y: {1}px for screen width: {2}px, screen height: {3}px, character size: {4}px - Use debug flag to disable bounds-checking")]
     CoordinatesOutOfBounds(i32, i32, i32, i32, u16),
     #[error("Unable to get screen resolution!")]
     NoScreenResolution,
     #[error("Could not flip buddy on horizontal axis: {0}(/vertical axis)")]
     FlipFailed(bool),
-    #[error("Unexpected animation type in sprites folder: {0}")]
-    UnexpectedAnimation(String),
+    #[error("Could not scale sprites to character size: {0}px")]
+    ScalingFailed(u16),
     #[error("Sprites cannot be found at path: {0}")]
     SpritesCannotBeFound(String),
+    #[error("Required animation {0} is missing from sprites at path: {1}")]
+    MissingAnimation(String, String),
+    #[error("Behaviour refers to unknown state: {0}")]
+    UnknownState(String),
+    #[error("Weights of a transition of state {0} must not all be zero nor add up to more than 4294967295")]
+    InvalidWeights(String),
+    #[error("Timer transition of state {0} needs a delay: `after` (or `until`) milliseconds greater than zero")]
+    MissingDelay(String),
+    #[error("Sprite pack has no animation called: {0}")]
+    UnknownAnimation(String),
+    #[error("There is no built-in sprite pack called: {0}")]
+    UnknownPack(String),
+    #[error("Invalid sprite descriptor: {0}")]
+    InvalidDescriptor(#[from] toml::de::Error),
+    #[error("Frame {1} of animation {0} lies outside of the sprite sheet")]
+    FrameOutOfBounds(String, i32),
+    #[error("Frame size of sprite sheet {0} is not specified")]
+    MissingFrameSize(String),
+    #[error("Frame size of sprite sheet {0} must be positive, not {1}x{2}px")]
+    InvalidFrameSize(String, i32, i32),
+    #[error("Animation {0} refers to a sprite sheet, but the sprite pack has none")]
+    MissingSheet(String),
+    #[error("Animation {0} cannot loop, it must be played once")]
+    MustPlayOnce(String),
+    #[error("Could not decode animated image: {0}")]
+    InvalidAnimation(#[from] image::ImageError),
+    #[error("Could not listen for commands at {0}: {1}")]
+    ControlSocketFailed(String, std::io::Error),
+    #[error("Refusing to use {0} for control sockets: it must be a directory owned by the current user that nobody else can write to")]
+    InsecureSocketDir(String),
+    #[error(
+        "There is no running buddy with ID: {0} - Use `buddy ctl list` to find running buddies"
+    )]
+    UnknownInstance(String),
+    #[error("Could not talk to buddy: {0}")]
+    ControlFailed(#[from] std::io::Error),
+    #[error("Invalid message: {0}")]
+    InvalidMessage(#[from] serde_json::Error),
+    #[error("Buddy refused the command: {0}")]
+    CommandFailed(String),
+    #[error("Could not save state: {0}")]
+    SaveFailed(String),
 }
@@ -1,10 +1,25 @@
 mod config;
+mod control;
 mod error;
 mod parse;
 mod render;
 
+use clap::Parser;
+use config::cli::{Cli, CliCommand};
+
 fn main() {
-    match parse::run() {
+    let cli = Cli::parse();
+
+    // talk to running buddies instead of starting one
+    if let Some(CliCommand::Ctl { target }) = cli.command {
+        if let Err(err) = control::run(target) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    match parse::run(cli) {
         Ok((config, sprites_path)) => render::render_character(config, sprites_path),
         Err(err) => eprintln!("{}", err),
     }
@@ -1,8 +1,9 @@
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use crate::config::{cli::Cli, Config};
+use crate::config::{cli::Cli, default, Config, BUILTIN_PREFIX};
 use crate::error::BuddyError;
-use clap::Parser;
 use regex::Regex;
 
 /// Parse cli args and match against config file
@@ -16,43 +17,77 @@ macro_rules! parse_args {
     }};
 }
 
-/// Parse [Cli] and config arguments. Returns [Config] structure and sprites path. [BuddyError] is returned in case of failirue (invalid config, invalid sprites path).
+/// Match [Cli] arguments against config file. Returns [Config] structure and sprites path. [BuddyError] is returned in case of failirue (invalid config).
 ///
 /// Note: sprites path in config structure remains None.
-pub(crate) fn run() -> Result<(Config, String), BuddyError> {
-    let cli = Cli::parse();
-
+pub(crate) fn run(cli: Cli) -> Result<(Config, String), BuddyError> {
     // load specific config file or default path.
-    let mut config: Config = match cli.config_path {
-        Some(config_path) => confy::load_path(config_path),
-        None => confy::load("buddy", Option::from("config")),
+    let config_path = match cli.config_path {
+        Some(config_path) => PathBuf::from(config_path),
+        None => confy::get_configuration_file_path("buddy", Option::from("config"))?,
+    };
+    let mut config: Config = confy::load_path(&config_path).map_err(BuddyError::from)?;
+
+    // migrate the update rate buddy used to move with, an explicit speed takes precedence
+    if let Some(movement_speed) = config.movement_speed.take() {
+        let speed = movement_speed.saturating_mul(default::LEGACY_STEP);
+        if !sets(&config_path, "speed") {
+            config.speed = speed;
+        }
+        println!(
+            "Warning: movement_speed in {} is deprecated, delete it and set speed (pixels per second) instead, e.g. speed = {}",
+            config_path.display(),
+            speed
+        );
+    }
+    if let Some(movement_speed) = cli.movement_speed {
+        config.speed = movement_speed.saturating_mul(default::LEGACY_STEP);
+        println!(
+            "Warning: -m/--movement-speed is deprecated, use --speed (pixels per second) instead: --speed {}",
+            config.speed
+        );
     }
-    .map_err(BuddyError::from)?;
 
     parse_args!(
         config,
         cli,
         sprites_path,
         character_size,
+        scaling_filter,
+        integer_scaling,
         fps,
-        movement_speed,
+        speed,
+        movement_step,
+        edge,
+        walk_on_windows,
         signal_frequency,
         automatic_reload,
         onclick_event_chance,
+        autonomous,
+        follow_cursor,
+        flee_cursor,
+        pointer_radius,
         x,
         y,
         left,
         flip_horizontal,
         flip_vertical,
+        auto_mirror,
+        restore_state,
         debug
     );
 
-    // check for existing sprites path
+    // built-in sprite pack selected by name
+    if let Some(pack) = cli.pack {
+        config.sprites_path = Some(format!("{}{}", BUILTIN_PREFIX, pack));
+    }
+
+    // fall back to default sprite pack
     let sprites_path = config
         .sprites_path
         .take()
         .and_then(|path| expand_env(path.replace("~", "$HOME")))
-        .ok_or(BuddyError::NoSprites)?;
+        .unwrap_or_else(|| default::SPRITES_PATH.to_string());
 
     Ok((config, sprites_path))
 }
@@ -66,3 +101,11 @@ fn expand_env(input: String) -> Option<String> {
         .to_string()
     })
 }
+
+/// Whether the config file at `path` sets `key` itself rather than falling back to its default.
+fn sets(path: &Path, key: &str) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| content.parse::<toml::Table>().ok())
+        .is_some_and(|table| table.contains_key(key))
+}
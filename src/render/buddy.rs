@@ -0,0 +1,1203 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+use clap::Parser;
+use gio::prelude::ApplicationExt;
+use glib::{monotonic_time, timeout_add_local_once};
+use gtk4::prelude::{GtkWindowExt, PopoverExt, WidgetExt, WidgetExtManual};
+use gtk4::{ApplicationWindow, Label, Popover, PositionType};
+use rand::Rng;
+
+use super::helpers::{clamp_position, screen_geometry, to_screen, update_input_region};
+use super::ipc::{self, windows};
+use super::movement::{self, Area, Heading, Ledge};
+use super::persist::{self, SavedState};
+use super::physics::{Body, Bounds, Tracker};
+use super::pointer::Pointer;
+use super::sprite::{
+    preload_images, Facing, PackInfo, Sprites, DOWN, DRAG, FALL, GOODBYE, LAND, RUN, UP,
+};
+use super::state::StateMachine;
+use crate::config::behaviour::{Movement, Trigger};
+use crate::config::cli::Cli;
+use crate::config::{Config, EdgePolicy, SignalAction};
+use crate::control::{Event, Status};
+use crate::error::BuddyError;
+use crate::parse;
+
+/// How long speech bubbles are shown.
+const SAY_DURATION: Duration = Duration::from_secs(4);
+
+/// How long buddy stays away after walking off the screen, if respawning (in microseconds).
+const RESPAWN_DELAY: i64 = 3_000_000;
+
+/// Interval of random triggers (in microseconds).
+const RANDOM_INTERVAL: i64 = 1_000_000;
+
+/// Interval of announcing buddy's position while it moves (in microseconds).
+const POSITION_INTERVAL: i64 = 100_000;
+
+/// Interval of asking the compositor for the windows buddy can stand on (in microseconds).
+const WINDOWS_INTERVAL: i64 = 500_000;
+
+/// Queries of the windows that may fail in a row before buddy stops walking on them. The interval doubles with every failure.
+const WINDOWS_ATTEMPTS: u32 = 5;
+
+/// Interval of checking the pointer's distance for pointer triggers (in microseconds).
+const POINTER_INTERVAL: i64 = 100_000;
+
+/// Callback on everything that happens to buddy. Returns whether to keep listening.
+type Listener = Box<dyn Fn(&Event) -> bool>;
+
+/// Motion overriding the current state, which is paused meanwhile.
+#[derive(Clone, Copy, PartialEq)]
+enum Motion {
+    /// Buddy follows its state.
+    None,
+    /// Buddy is held by the pointer.
+    Dragged,
+    /// Buddy is in the air.
+    Falling,
+    /// Buddy just hit the ground.
+    Landing,
+    /// Buddy walked off the screen and waits to respawn.
+    Away,
+    /// Buddy plays an animation once on request.
+    Playing,
+}
+
+/// Points in time (monotonic, in microseconds) at which buddy needs to be updated.
+#[derive(Default)]
+struct Schedule {
+    /// Next frame of the animation.
+    frame: Option<i64>,
+    /// Timer transitions of the current state, by index.
+    timers: Vec<(i64, usize)>,
+    random: Option<i64>,
+    /// Next check of the pointer's distance.
+    pointer: Option<i64>,
+    respawn: Option<i64>,
+    /// Next automatic reload of the sprites.
+    reload: Option<i64>,
+    /// Next query of the windows buddy can stand on.
+    windows: Option<i64>,
+}
+
+impl Schedule {
+    /// Earliest point in time buddy needs to be updated at, if any.
+    fn next(&self) -> Option<i64> {
+        self.timers
+            .iter()
+            .map(|(time, _)| *time)
+            .chain(
+                [
+                    self.frame,
+                    self.random,
+                    self.pointer,
+                    self.respawn,
+                    self.reload,
+                    self.windows,
+                ]
+                .into_iter()
+                .flatten(),
+            )
+            .min()
+    }
+}
+
+/// Buddy's behaviour together with the widget showing it.
+///
+/// Buddy is updated by a single loop on the frame clock of its window. The loop only runs every frame while buddy moves and otherwise sleeps until the next update is due.
+pub(super) struct Buddy {
+    pub(super) window: ApplicationWindow,
+    pub(super) character: gtk4::Picture,
+    pub(super) sprites: RefCell<Sprites>,
+    machine: RefCell<StateMachine>,
+    /// Frame of the current animation.
+    frame: Cell<usize>,
+    schedule: RefCell<Schedule>,
+    /// Whether the update loop runs every frame.
+    ticking: Cell<bool>,
+    /// Frame time of the last update, if ticking.
+    last_frame: Cell<Option<i64>>,
+    /// Incremented whenever buddy is woken up. Pending wake ups compare against it and are dropped.
+    sleep: Cell<u64>,
+    /// Whether the sprites are to be reloaded on the next update.
+    reload: Cell<bool>,
+    /// Whether buddy is frozen in place.
+    paused: Cell<bool>,
+    /// Whether buddy is about to close.
+    quitting: Cell<bool>,
+    /// Speech bubble above buddy.
+    bubble: Popover,
+    /// Text of the speech bubble.
+    speech: Label,
+    /// Incremented whenever buddy says something. Pending hiding of the speech bubble compares against it and is dropped.
+    said: Cell<u64>,
+    /// Called on everything that happens to buddy.
+    listeners: RefCell<Vec<Listener>>,
+    /// Position last announced to the listeners and when.
+    announced: Cell<(i64, (i32, i32))>,
+    /// Pixels per second buddy moves with.
+    speed: Cell<u32>,
+    /// Animation played on request.
+    played: RefCell<String>,
+    /// Direction buddy moves into.
+    pub(super) left: Cell<bool>,
+    /// Direction buddy last moved into, determining the animation of moving upwards and downwards.
+    heading: Cell<Heading>,
+    /// Point buddy is wandering to.
+    target: Cell<Option<(i32, i32)>>,
+    /// Distance covered since the last step, in pixels.
+    distance: Cell<f64>,
+    motion: Cell<Motion>,
+    /// Position and velocity while falling.
+    body: RefCell<Option<Body>>,
+    /// Recent positions while dragged.
+    tracker: RefCell<Tracker>,
+    pub(super) pointer: Pointer,
+    /// Tops of the windows buddy can stand on, if walking on windows.
+    ledges: RefCell<Vec<Ledge>>,
+    /// Queries of the windows that failed in a row.
+    ledge_failures: Cell<u32>,
+    /// Lowest y position, placing the anchor point of the sprites at the bottom of the screen.
+    ground: i32,
+    pub(super) character_size: i32,
+    /// Screen resolution (width, height).
+    pub(super) screen: (i32, i32),
+    sprites_path: RefCell<String>,
+    config: RefCell<Config>,
+}
+
+impl Buddy {
+    pub(super) fn new(
+        window: ApplicationWindow,
+        character: gtk4::Picture,
+        sprites: Sprites,
+        machine: StateMachine,
+        sprites_path: String,
+        config: &Config,
+        screen: (i32, i32),
+    ) -> Rc<Self> {
+        let character_size = config.character_size as i32;
+        // keep the anchor point of the sprites on the bottom of the screen
+        let ground = -((1.0 - sprites.info.anchor.1) * character_size as f64) as i32;
+
+        let speech = Label::new(None);
+        let bubble = Popover::new();
+        bubble.set_child(Some(&speech));
+        bubble.set_position(PositionType::Top);
+        bubble.set_autohide(false);
+        bubble.set_parent(&character);
+
+        Rc::new(Buddy {
+            pointer: Pointer::new(&window),
+            window,
+            character,
+            sprites: RefCell::new(sprites),
+            machine: RefCell::new(machine),
+            frame: Cell::new(0),
+            schedule: RefCell::new(Schedule::default()),
+            ticking: Cell::new(false),
+            last_frame: Cell::new(None),
+            sleep: Cell::new(0),
+            reload: Cell::new(false),
+            paused: Cell::new(false),
+            quitting: Cell::new(false),
+            bubble,
+            speech,
+            said: Cell::new(0),
+            listeners: RefCell::new(Vec::new()),
+            announced: Cell::new((0, (0, 0))),
+            speed: Cell::new(config.speed),
+            played: RefCell::new(String::new()),
+            left: Cell::new(config.left),
+            heading: Cell::new(Heading::Horizontal),
+            target: Cell::new(None),
+            distance: Cell::new(0.0),
+            motion: Cell::new(Motion::None),
+            body: RefCell::new(None),
+            tracker: RefCell::new(Tracker::default()),
+            ledges: RefCell::new(Vec::new()),
+            ledge_failures: Cell::new(0),
+            ground,
+            character_size,
+            screen,
+            sprites_path: RefCell::new(sprites_path),
+            config: RefCell::new(config.clone()),
+        })
+    }
+
+    /// Start animating the initial state, falling down first if buddy starts in the air.
+    pub(super) fn start(self: &Rc<Self>) {
+        self.schedule.borrow_mut().reload = self.reload_interval().map(|interval| now() + interval);
+
+        if self.config.borrow().walk_on_windows {
+            self.update_ledges(now());
+        }
+        self.settle((0.0, 0.0));
+    }
+
+    /// Reload the sprites on the next update.
+    pub(super) fn request_reload(self: &Rc<Self>) {
+        self.reload.set(true);
+        self.wake();
+    }
+
+    /// Whether buddy moves along the screen in the current state.
+    pub(super) fn moving(&self) -> bool {
+        self.motion.get() == Motion::None && self.machine.borrow().state().moving
+    }
+
+    /// Position of buddy's bottom left corner (x from the left, y from the bottom of the screen).
+    pub(super) fn position(&self) -> (i32, i32) {
+        (
+            self.character.margin_start(),
+            self.character.margin_bottom(),
+        )
+    }
+
+    /// Place buddy's bottom left corner at `x`, `y` (from the bottom of the screen) and move the click-able section along.
+    pub(super) fn move_to(&self, x: i32, y: i32) {
+        self.character.set_margin_start(x);
+        self.character.set_margin_bottom(y);
+        self.announce_position(false);
+
+        // the window may not be allocated yet
+        let height = Some(self.window.height())
+            .filter(|height| *height > 0)
+            .unwrap_or(self.screen.1);
+        update_input_region(
+            &self.window,
+            self.character_size,
+            x,
+            height - y - self.character_size,
+        );
+    }
+
+    /// Make sure buddy is updated on the next frame, starting the update loop if it sleeps.
+    fn wake(self: &Rc<Self>) {
+        // drop pending wake ups
+        self.sleep.set(self.sleep.get() + 1);
+
+        if self.ticking.replace(true) {
+            return;
+        }
+        self.last_frame.set(None);
+
+        let buddy = Rc::clone(self);
+        // the window stays mapped even if buddy is hidden
+        self.window.add_tick_callback(move |_window, clock| {
+            if buddy.update(clock.frame_time()) {
+                gtk4::glib::ControlFlow::Continue
+            } else {
+                buddy.ticking.set(false);
+                gtk4::glib::ControlFlow::Break
+            }
+        });
+    }
+
+    /// Wake buddy up at `time`, unless it is woken up earlier.
+    fn sleep_until(self: &Rc<Self>, time: i64) {
+        let sleep = self.sleep.get() + 1;
+        self.sleep.set(sleep);
+
+        let buddy = Rc::clone(self);
+        timeout_add_local_once(
+            Duration::from_micros((time - now()).max(0) as u64),
+            move || {
+                if buddy.sleep.get() == sleep {
+                    buddy.wake();
+                }
+            },
+        );
+    }
+
+    /// Advance physics, movement, timers, animation and reloads to `now`. Returns whether buddy needs to be updated on the next frame again, otherwise it sleeps until its next update is due.
+    fn update(self: &Rc<Self>, now: i64) -> bool {
+        // frozen until resumed
+        if self.paused.get() {
+            return false;
+        }
+
+        let elapsed = self
+            .last_frame
+            .replace(Some(now))
+            .map(|last_frame| Duration::from_micros((now - last_frame).max(0) as u64))
+            .unwrap_or_default();
+
+        if self.motion.get() == Motion::Falling {
+            self.fall(elapsed);
+        }
+
+        self.advance(elapsed);
+        self.fire_timers(now);
+        self.play(now);
+
+        let reload = self
+            .schedule
+            .borrow()
+            .reload
+            .is_some_and(|time| time <= now);
+        if self.reload.replace(false) || reload {
+            self.reload_sprites(now);
+        }
+
+        if self.moving() || self.motion.get() == Motion::Falling {
+            return true;
+        }
+
+        // where buddy came to rest
+        self.announce_position(true);
+
+        let next = self.schedule.borrow().next();
+        if let Some(next) = next {
+            self.sleep_until(next);
+        }
+        false
+    }
+
+    /// Take the timer transitions and random triggers of the current state that are due at `now`, as well as respawn.
+    fn fire_timers(self: &Rc<Self>, now: i64) {
+        let timer = {
+            let mut schedule = self.schedule.borrow_mut();
+            schedule
+                .timers
+                .iter()
+                .position(|(time, _)| *time <= now)
+                .map(|position| schedule.timers.remove(position).1)
+        };
+
+        if let Some(index) = timer {
+            let transitioned = self.machine.borrow_mut().take(index);
+            if transitioned {
+                self.change();
+                return;
+            }
+        }
+
+        let random = self
+            .schedule
+            .borrow()
+            .random
+            .is_some_and(|time| time <= now);
+        if random {
+            self.schedule.borrow_mut().random = Some(now + RANDOM_INTERVAL);
+            if self.trigger(Trigger::Random) {
+                return;
+            }
+        }
+
+        let pointer = self
+            .schedule
+            .borrow()
+            .pointer
+            .is_some_and(|time| time <= now);
+        if pointer {
+            self.schedule.borrow_mut().pointer = Some(now + POINTER_INTERVAL);
+
+            let radius = self.config.borrow().pointer_radius as f64;
+            // buddy going after the pointer along the ground cannot get any closer than right below it
+            let horizontal = self.machine.borrow().grounded_targets(Trigger::PointerAway);
+
+            if self
+                .pointer_distance(now, false)
+                .is_some_and(|distance| distance <= radius)
+                && self.trigger(Trigger::PointerNear)
+            {
+                return;
+            }
+            if self
+                .pointer_distance(now, horizontal)
+                .is_some_and(|distance| distance > radius)
+                && self.trigger(Trigger::PointerAway)
+            {
+                return;
+            }
+        }
+
+        let windows = self
+            .schedule
+            .borrow()
+            .windows
+            .is_some_and(|time| time <= now);
+        if windows {
+            self.update_ledges(now);
+        }
+
+        let respawn = self
+            .schedule
+            .borrow()
+            .respawn
+            .is_some_and(|time| time <= now);
+        if respawn {
+            let area = self.area();
+            self.move_to(area.random().0, area.ground);
+            self.character.set_visible(true);
+            self.motion.set(Motion::None);
+            self.enter();
+        }
+    }
+
+    /// Ask the compositor where the windows buddy can stand on are and schedule the next query. Buddy falls down if the window it stands on has been moved or closed. Failed queries are retried with backoff, buddy stops walking on windows after [WINDOWS_ATTEMPTS] failures in a row or if the compositor cannot be asked at all.
+    fn update_ledges(self: &Rc<Self>, now: i64) {
+        if !ipc::available() {
+            self.stop_ledges("The compositor does not tell where windows are, buddy can only walk on them with Hyprland or Sway.".to_string());
+            return;
+        }
+
+        let geometry = screen_geometry(&self.window);
+        let Some((geometry, windows)) = geometry.and_then(|geometry| {
+            let windows = windows((geometry.x(), geometry.y()))?;
+            Some((geometry, windows))
+        }) else {
+            let failures = self.ledge_failures.get() + 1;
+            self.ledge_failures.set(failures);
+            if failures >= WINDOWS_ATTEMPTS {
+                self.stop_ledges(format!(
+                    "The compositor did not tell where windows are {} times in a row, buddy no longer walks on them.",
+                    failures
+                ));
+            } else {
+                // the queries block, back off while the compositor does not answer and keep the ledges meanwhile
+                self.schedule.borrow_mut().windows = Some(now + (WINDOWS_INTERVAL << failures));
+            }
+            return;
+        };
+        self.ledge_failures.set(0);
+        self.schedule.borrow_mut().windows = Some(now + WINDOWS_INTERVAL);
+
+        *self.ledges.borrow_mut() = windows
+            .into_iter()
+            .map(|(x, y, width, _)| {
+                let (left, top) = to_screen(&geometry, x, y);
+                Ledge {
+                    left,
+                    right: left + width,
+                    top,
+                }
+            })
+            // buddy needs room to stand on top of the window
+            .filter(|ledge| {
+                ledge.right > 0
+                    && ledge.left < self.screen.0
+                    && ledge.top > 0
+                    && ledge.top + self.ground + self.character_size <= self.screen.1
+            })
+            .collect();
+
+        if self.motion.get() == Motion::None && self.grounded() && !self.supported() {
+            self.settle((0.0, 0.0));
+        }
+    }
+
+    /// Stop asking the compositor where windows are, telling why with `message`. Buddy falls down if it stands on a window.
+    fn stop_ledges(self: &Rc<Self>, message: String) {
+        self.schedule.borrow_mut().windows = None;
+        self.ledges.borrow_mut().clear();
+        println!("Warning: {}", message);
+        self.emit(Event::Error { message });
+
+        if self.motion.get() == Motion::None && self.grounded() && !self.supported() {
+            self.settle((0.0, 0.0));
+        }
+    }
+
+    /// Replace the sprites with freshly loaded ones and schedule the next automatic reload.
+    fn reload_sprites(&self, now: i64) {
+        match preload_images(&self.sprites_path.borrow(), &self.config.borrow()) {
+            Ok(sprites) => *self.sprites.borrow_mut() = sprites,
+            Err(err) => {
+                let message = format!("Could not update sprites: {}", err);
+                println!("Warning: {}", message);
+                self.emit(Event::Error { message });
+            }
+        }
+        self.schedule.borrow_mut().reload = self.reload_interval().map(|interval| now + interval);
+    }
+
+    /// Microseconds between automatic reloads, if enabled.
+    fn reload_interval(&self) -> Option<i64> {
+        let config = self.config.borrow();
+        config
+            .automatic_reload
+            .then(|| 1_000_000 / config.signal_frequency.max(1) as i64)
+    }
+
+    /// Move as far as buddy gets at its speed within `elapsed`, in steps of the configured size.
+    fn advance(self: &Rc<Self>, elapsed: Duration) {
+        if !self.moving() {
+            self.distance.set(0.0);
+            return;
+        }
+
+        let step = self.config.borrow().movement_step.max(1) as f64;
+        let mut distance = self.distance.get() + self.speed.get() as f64 * elapsed.as_secs_f64();
+
+        while distance >= step && self.moving() {
+            self.step();
+            distance -= step;
+        }
+        self.distance.set(distance);
+    }
+
+    /// Move one step according to the movement of the current state.
+    fn step(self: &Rc<Self>) {
+        let step = self.config.borrow().movement_step.max(1) as i32;
+
+        let movement = self.machine.borrow().state().movement;
+        let position = self.position();
+        let left = self.left.get();
+        let area = self.area();
+
+        let (next, trigger) = match movement {
+            Movement::Walk => {
+                let (next, edge) = movement::walk(
+                    position,
+                    left,
+                    step,
+                    self.config.borrow().edge,
+                    self.character_size,
+                    self.screen.0,
+                );
+
+                if edge {
+                    match self.config.borrow().edge {
+                        EdgePolicy::Wrap => {}
+                        EdgePolicy::Bounce => self.left.set(!left),
+                        EdgePolicy::Stop => {
+                            self.move_to(next.0, next.1);
+                            if !self.trigger(Trigger::Edge) {
+                                self.machine.borrow_mut().reset();
+                                self.change();
+                            }
+                            return;
+                        }
+                        EdgePolicy::Respawn => {
+                            self.move_to(next.0, next.1);
+                            self.respawn();
+                            return;
+                        }
+                    }
+                }
+                (next, edge.then_some(Trigger::Edge))
+            }
+            Movement::Wander => {
+                let target = self.target.get().unwrap_or_else(|| area.random());
+                let (next, arrived) = movement::approach(position, target, step);
+                // pick a new point once arrived
+                self.target.set((!arrived).then_some(target));
+
+                if next.0 != position.0 {
+                    self.left.set(next.0 < position.0);
+                }
+                (next, arrived.then_some(Trigger::Arrive))
+            }
+            Movement::Climb => {
+                let (next, top) = movement::climb(position, left, step, &area);
+                (next, top.then_some(Trigger::Edge))
+            }
+            Movement::Follow | Movement::Chase => {
+                // wait for the pointer to show up
+                let Some((pointer_x, pointer_y)) = self.pointer.position(now()) else {
+                    return;
+                };
+
+                // center buddy on the pointer
+                let half = self.character_size / 2;
+                let x = (pointer_x - half).clamp(area.left, area.right.max(area.left));
+                let target = if movement == Movement::Follow {
+                    (x, position.1)
+                } else {
+                    (
+                        x,
+                        (pointer_y - half).clamp(area.ground, area.ceiling.max(area.ground)),
+                    )
+                };
+
+                let (next, arrived) = movement::approach(position, target, step);
+                if next.0 != position.0 {
+                    self.left.set(next.0 < position.0);
+                }
+                (next, arrived.then_some(Trigger::Arrive))
+            }
+            Movement::Flee => {
+                // run away from the pointer, or keep running if it is gone
+                if let Some((pointer_x, _)) = self.pointer.position(now()) {
+                    self.left
+                        .set(pointer_x > position.0 + self.character_size / 2);
+                }
+
+                // cornered buddy stays at the edge of the screen
+                let (next, edge) = movement::walk(
+                    position,
+                    self.left.get(),
+                    step,
+                    EdgePolicy::Stop,
+                    self.character_size,
+                    self.screen.0,
+                );
+                (next, edge.then_some(Trigger::Edge))
+            }
+        };
+
+        self.move_to(next.0, next.1);
+
+        // walk off the window buddy stood on
+        if movement.grounded() && !self.supported() {
+            let speed = self.speed.get() as f64;
+            self.settle((if self.left.get() { -speed } else { speed }, 0.0));
+            return;
+        }
+
+        // show the sprites of the new direction right away
+        let heading = Heading::new(position, next);
+        if self.heading.replace(heading) != heading || self.left.get() != left {
+            self.show(now());
+        }
+
+        if let Some(trigger) = trigger {
+            self.trigger(trigger);
+        }
+    }
+
+    /// Distance between the pointer and the center of buddy at `now`, only along the x-axis if `horizontal`. None if the pointer position is unknown.
+    fn pointer_distance(&self, now: i64, horizontal: bool) -> Option<f64> {
+        let (pointer_x, pointer_y) = self.pointer.position(now)?;
+        let (x, y) = self.position();
+        let half = self.character_size / 2;
+
+        let (dx, dy) = ((pointer_x - x - half) as f64, (pointer_y - y - half) as f64);
+        Some(if horizontal { dx.abs() } else { dx.hypot(dy) })
+    }
+
+    /// Lowest y position of buddy at `x` coming down from `y`: standing on the highest window below whose top buddy's center is above, otherwise on the bottom of the screen.
+    fn floor(&self, (x, y): (i32, i32)) -> i32 {
+        let center = x + self.character_size / 2;
+
+        self.ledges
+            .borrow()
+            .iter()
+            .filter(|ledge| (ledge.left..=ledge.right).contains(&center))
+            .map(|ledge| ledge.top + self.ground)
+            .filter(|floor| *floor <= y)
+            .max()
+            .unwrap_or(self.ground)
+            .max(self.ground)
+    }
+
+    /// Whether buddy stands on the bottom of the screen or a window. Without gravity, buddy stays wherever it is.
+    fn supported(&self) -> bool {
+        let position = self.position();
+        self.config.borrow().physics.gravity == 0 || position.1 <= self.floor(position)
+    }
+
+    /// Whether the current state keeps buddy on the ground.
+    fn grounded(&self) -> bool {
+        let machine = self.machine.borrow();
+        let state = machine.state();
+        !state.moving || state.movement.grounded()
+    }
+
+    /// Area buddy can move within without leaving the screen.
+    fn area(&self) -> Area {
+        Area {
+            left: 0,
+            right: self.screen.0 - self.character_size,
+            ground: self.ground,
+            ceiling: self.screen.1 - self.character_size,
+        }
+    }
+
+    /// Hide buddy until it comes back at a random position along the ground.
+    fn respawn(self: &Rc<Self>) {
+        self.overlay(Motion::Away);
+        self.character.set_visible(false);
+        self.schedule.borrow_mut().respawn = Some(now() + RESPAWN_DELAY);
+    }
+
+    /// Notify the state machine of `trigger`, entering the new state if a transition is taken. Ignored while the state is paused. Returns whether a transition was taken.
+    pub(super) fn trigger(self: &Rc<Self>, trigger: Trigger) -> bool {
+        if self.motion.get() != Motion::None || self.paused.get() {
+            return false;
+        }
+
+        let transitioned = self.machine.borrow_mut().trigger(trigger);
+
+        if transitioned {
+            self.change();
+        }
+        transitioned
+    }
+
+    /// Pick buddy up, pausing its state and playing the drag animation until it is put down. Returns whether buddy could be picked up, which it cannot while paused.
+    pub(super) fn pick_up(self: &Rc<Self>) -> bool {
+        if self.paused.get() {
+            return false;
+        }
+
+        self.tracker.borrow_mut().clear();
+        self.overlay(Motion::Dragged);
+        true
+    }
+
+    /// Move held buddy to `x`, `y`, keeping track of how fast it is moved.
+    pub(super) fn drag_to(&self, x: i32, y: i32) {
+        self.move_to(x, y);
+        self.tracker.borrow_mut().push((x, y));
+    }
+
+    /// Let go of buddy, throwing it with the velocity it was last dragged with. Buddy resumes its state once it comes to rest.
+    pub(super) fn put_down(self: &Rc<Self>) {
+        let velocity = self.tracker.borrow().velocity();
+        self.settle(velocity);
+    }
+
+    /// Whether buddy is held by the pointer.
+    pub(super) fn dragged(&self) -> bool {
+        self.motion.get() == Motion::Dragged
+    }
+
+    /// Pause the current state to play the animation called `name` once. Fails with [BuddyError] if the sprite pack has no such animation.
+    pub(super) fn play_animation(self: &Rc<Self>, name: &str) -> Result<(), BuddyError> {
+        if !self.sprites.borrow().has(name) {
+            return Err(BuddyError::UnknownAnimation(name.to_string()));
+        }
+
+        *self.played.borrow_mut() = name.to_string();
+        self.character.set_visible(true);
+        self.overlay(Motion::Playing);
+        Ok(())
+    }
+
+    /// Place buddy's bottom left corner at `x`, `y` (from the bottom of the screen), kept within the screen. Buddy falls down from there if in the air. Ignored while dragged.
+    pub(super) fn place(self: &Rc<Self>, x: i32, y: i32) {
+        if self.dragged() {
+            return;
+        }
+
+        let (x, y) = clamp_position(x, y, self.character_size, self.screen.0, self.screen.1);
+        self.move_to(x, y);
+        self.character.set_visible(true);
+        self.settle((0.0, 0.0));
+    }
+
+    /// Change to `state`, entered once buddy is neither dragged nor falling. Fails with [BuddyError] if the behaviour has no such state.
+    pub(super) fn set_state(self: &Rc<Self>, state: &str) -> Result<(), BuddyError> {
+        self.machine.borrow_mut().set(state)?;
+
+        if self.motion.get() == Motion::None {
+            self.change();
+        } else {
+            self.emit(Event::State {
+                state: state.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Handle a click on buddy.
+    pub(super) fn click(self: &Rc<Self>) {
+        self.emit(Event::Clicked);
+        self.trigger(Trigger::Click);
+    }
+
+    /// Show `text` in a speech bubble above buddy for a few seconds.
+    pub(super) fn say(self: &Rc<Self>, text: &str) {
+        self.speech.set_text(text);
+        self.bubble.popup();
+
+        let said = self.said.get() + 1;
+        self.said.set(said);
+
+        let buddy = Rc::clone(self);
+        timeout_add_local_once(SAY_DURATION, move || {
+            if buddy.said.get() == said {
+                buddy.bubble.popdown();
+            }
+        });
+    }
+
+    /// Action configured for `signal`.
+    pub(super) fn signal_action(&self, signal: i32) -> SignalAction {
+        self.config.borrow().signals.action(signal)
+    }
+
+    /// Whether buddy is frozen in place.
+    pub(super) fn paused(&self) -> bool {
+        self.paused.get()
+    }
+
+    /// Freeze buddy in place if `paused`, ignoring clicks and drags, or let it carry on.
+    pub(super) fn set_paused(self: &Rc<Self>, paused: bool) {
+        if self.paused.replace(paused) == paused {
+            return;
+        }
+
+        if !paused {
+            self.wake();
+        }
+        self.emit(Event::Paused { paused });
+    }
+
+    /// Read the configuration file and command line arguments again and apply them, apart from the character size, which requires a restart. The sprites are reloaded and the behaviour starts over.
+    pub(super) fn reload_config(self: &Rc<Self>) {
+        let result = parse::run(Cli::parse()).and_then(|(mut config, sprites_path)| {
+            config.character_size = self.character_size as u16;
+            let sprites = preload_images(&sprites_path, &config)?;
+            let machine = StateMachine::for_config(&config, &sprites)?;
+            Ok((config, sprites_path, sprites, machine))
+        });
+
+        let (config, sprites_path, sprites, machine) = match result {
+            Ok(result) => result,
+            Err(err) => {
+                let message = format!("Could not reload configuration: {}", err);
+                println!("Warning: {}", message);
+                self.emit(Event::Error { message });
+                return;
+            }
+        };
+
+        let walk_on_windows = config.walk_on_windows;
+        self.speed.set(config.speed);
+        *self.config.borrow_mut() = config;
+        *self.sprites_path.borrow_mut() = sprites_path;
+        *self.sprites.borrow_mut() = sprites;
+        *self.machine.borrow_mut() = machine;
+
+        let now = now();
+        self.schedule.borrow_mut().reload = self.reload_interval().map(|interval| now + interval);
+        if walk_on_windows {
+            self.ledge_failures.set(0);
+            self.update_ledges(now);
+        } else {
+            self.ledges.borrow_mut().clear();
+            self.schedule.borrow_mut().windows = None;
+        }
+
+        if self.motion.get() == Motion::None {
+            self.change();
+        }
+    }
+
+    /// Close buddy gracefully, playing the goodbye animation first if the sprite pack provides one. Closes right away if asked again meanwhile.
+    pub(super) fn quit(self: &Rc<Self>) {
+        if self.quitting.replace(true) || !self.sprites.borrow().has(GOODBYE) {
+            self.exit();
+            return;
+        }
+
+        // say goodbye even if paused or away
+        self.paused.set(false);
+        self.character.set_visible(true);
+        *self.played.borrow_mut() = GOODBYE.to_string();
+        self.overlay(Motion::Playing);
+    }
+
+    /// Save where buddy is and what it is doing, then close the application.
+    fn exit(&self) {
+        let (x, y) = self.position();
+        let state = SavedState {
+            x,
+            y,
+            left: self.left.get(),
+            state: self.machine.borrow().current().to_string(),
+        };
+
+        if let Err(err) = persist::save(&state) {
+            println!("Warning: {}", err);
+        }
+
+        if let Some(application) = self.window.application() {
+            application.quit();
+        }
+    }
+
+    /// Call `listener` on everything that happens to buddy from now on, until it returns false.
+    pub(super) fn subscribe(&self, listener: impl Fn(&Event) -> bool + 'static) {
+        self.listeners.borrow_mut().push(Box::new(listener));
+    }
+
+    fn emit(&self, event: Event) {
+        self.listeners
+            .borrow_mut()
+            .retain(|listener| listener(&event));
+    }
+
+    /// Announce buddy's position if it has changed, at most once per interval unless `force`d.
+    fn announce_position(&self, force: bool) {
+        let now = now();
+        let position = self.position();
+        let (time, announced) = self.announced.get();
+
+        if position != announced && (force || now - time >= POSITION_INTERVAL) {
+            self.announced.set((now, position));
+            self.emit(Event::Moved {
+                x: position.0,
+                y: position.1,
+            });
+        }
+    }
+
+    /// Change the pixels per second buddy moves with.
+    pub(super) fn set_speed(&self, speed: u32) {
+        self.speed.set(speed);
+    }
+
+    /// What buddy is doing right now.
+    pub(super) fn status(&self) -> Status {
+        let (x, y) = self.position();
+
+        Status {
+            pid: std::process::id(),
+            state: self.machine.borrow().current().to_string(),
+            animation: self.animation(),
+            x,
+            y,
+            left: self.left.get(),
+            speed: self.speed.get(),
+            paused: self.paused.get(),
+        }
+    }
+
+    /// Fly with `velocity` and fall down until coming to rest if buddy is moving or in the air (and there is gravity), otherwise resume the current state right away.
+    fn settle(self: &Rc<Self>, velocity: (f64, f64)) {
+        if self.config.borrow().physics.gravity == 0 || (self.supported() && velocity == (0.0, 0.0))
+        {
+            self.motion.set(Motion::None);
+            self.enter();
+            return;
+        }
+
+        *self.body.borrow_mut() = Some(Body::new(
+            self.position(),
+            velocity,
+            &self.config.borrow().physics,
+        ));
+        self.overlay(Motion::Falling);
+    }
+
+    /// Advance falling buddy by `elapsed`, landing once it comes to rest.
+    fn fall(self: &Rc<Self>, elapsed: Duration) {
+        let bounds = Bounds {
+            left: 0.0,
+            right: (self.screen.0 - self.character_size) as f64,
+            ground: self.floor(self.position()) as f64,
+            ceiling: (self.screen.1 - self.character_size) as f64,
+        };
+
+        let (position, landed) = {
+            let mut body = self.body.borrow_mut();
+            let Some(body) = body.as_mut() else {
+                return;
+            };
+
+            let landed = body.step(
+                &self.config.borrow().physics,
+                &bounds,
+                elapsed.as_secs_f64(),
+            );
+            ((body.x as i32, body.y as i32), landed)
+        };
+
+        self.move_to(position.0, position.1);
+
+        if landed {
+            *self.body.borrow_mut() = None;
+            self.land();
+        }
+    }
+
+    /// Play the landing animation, if the sprite pack provides one, before resuming the current state.
+    fn land(self: &Rc<Self>) {
+        if self.sprites.borrow().has(LAND) {
+            self.overlay(Motion::Landing);
+        } else {
+            self.motion.set(Motion::None);
+            self.enter();
+        }
+    }
+
+    /// Pause the current state and play the animation of `motion` instead.
+    fn overlay(self: &Rc<Self>, motion: Motion) {
+        self.motion.set(motion);
+        self.restart();
+        self.show(now());
+        self.wake();
+    }
+
+    /// Drop the timers of the current state and start over at the first frame.
+    fn restart(&self) {
+        let mut schedule = self.schedule.borrow_mut();
+        *schedule = Schedule {
+            reload: schedule.reload,
+            windows: schedule.windows,
+            ..Default::default()
+        };
+        self.frame.set(0);
+    }
+
+    /// Enter the state the machine has just changed to, turning around first if the state says so. States that keep buddy on the ground let it fall down first.
+    fn change(self: &Rc<Self>) {
+        let state = self.machine.borrow().current().to_string();
+        self.emit(Event::State { state });
+
+        if self.machine.borrow().state().turn {
+            self.left.set(!self.left.get());
+        }
+
+        if self.grounded() && !self.supported() {
+            self.settle((0.0, 0.0));
+        } else {
+            self.enter();
+        }
+    }
+
+    /// Restart the animation and timers for the current state.
+    fn enter(self: &Rc<Self>) {
+        self.announce_position(true);
+        self.restart();
+        self.heading.set(Heading::Horizontal);
+        self.target.set(None);
+
+        let now = now();
+        {
+            let machine = self.machine.borrow();
+            let mut schedule = self.schedule.borrow_mut();
+
+            for (index, transition) in machine.state().transitions.iter().enumerate() {
+                match transition.trigger {
+                    Trigger::Timer => {
+                        let after = transition.after.unwrap_or_default();
+                        // random duration within the range, if any
+                        let after = match transition.until {
+                            Some(until) if until > after => {
+                                rand::thread_rng().gen_range(after..=until)
+                            }
+                            _ => after,
+                        };
+                        schedule.timers.push((now + after as i64 * 1000, index));
+                    }
+                    Trigger::Random => schedule.random = Some(now + RANDOM_INTERVAL),
+                    Trigger::PointerAway | Trigger::PointerNear => {
+                        schedule.pointer = Some(now + POINTER_INTERVAL)
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        self.show(now);
+        self.wake();
+    }
+
+    /// Whether sprites need to be mirrored to face the direction buddy moves into.
+    fn mirrored(&self, info: &PackInfo) -> bool {
+        // flipped sprites face the other way
+        let facing_left = (info.facing == Facing::Left) != self.config.borrow().flip_horizontal;
+        self.config.borrow().auto_mirror && self.left.get() != facing_left
+    }
+
+    /// Name of the animation to play: the one of the motion, if any, otherwise the one of the current state and direction.
+    fn animation(&self) -> String {
+        match self.motion.get() {
+            Motion::None | Motion::Away => {
+                let machine = self.machine.borrow();
+                let state = machine.state();
+
+                let vertical = match self.heading.get() {
+                    Heading::Horizontal => None,
+                    Heading::Up => state
+                        .up
+                        .clone()
+                        .or_else(|| self.sprites.borrow().has(UP).then(|| UP.to_string())),
+                    Heading::Down => state
+                        .down
+                        .clone()
+                        .or_else(|| self.sprites.borrow().has(DOWN).then(|| DOWN.to_string())),
+                };
+                vertical.unwrap_or_else(|| {
+                    // moving states fall back to running rather than idling
+                    if state.moving && !self.sprites.borrow().has(&state.animation) {
+                        RUN.to_string()
+                    } else {
+                        state.animation.clone()
+                    }
+                })
+            }
+            Motion::Dragged => DRAG.to_string(),
+            Motion::Falling => FALL.to_string(),
+            Motion::Landing => LAND.to_string(),
+            Motion::Playing => self.played.borrow().clone(),
+        }
+    }
+
+    /// Show the current frame and schedule the next one once its duration has passed. Single frames of looping animations stay until something changes, unless played on request.
+    fn show(&self, now: i64) {
+        let sprites = self.sprites.borrow();
+        let animation = sprites.get(&self.animation());
+        let frame = self.frame.get();
+
+        self.character
+            .set_paintable(Some(animation.frame(frame, self.mirrored(&sprites.info))));
+
+        self.schedule.borrow_mut().frame = (animation.frames.len() > 1
+            || !animation.looping
+            || self.motion.get() == Motion::Playing)
+            .then(|| {
+                now + animation
+                    .duration(frame, self.config.borrow().fps)
+                    .as_micros() as i64
+            });
+    }
+
+    /// Move on to the next frame if the current one has been shown long enough. When an animation that does not loop has finished, the animation end is triggered. Animations played on request end after their last frame in any case.
+    fn play(self: &Rc<Self>, now: i64) {
+        if self.schedule.borrow().frame.is_none_or(|time| time > now) {
+            return;
+        }
+
+        let next = {
+            let sprites = self.sprites.borrow();
+            let animation = sprites.get(&self.animation());
+            let frame = self.frame.get();
+
+            if self.motion.get() == Motion::Playing && frame + 1 >= animation.frames.len() {
+                None
+            } else {
+                animation.next_frame(frame)
+            }
+        };
+
+        match next {
+            Some(next) => {
+                self.frame.set(next);
+                self.show(now);
+            }
+            // keep showing the last frame unless the state changes
+            None => {
+                self.schedule.borrow_mut().frame = None;
+
+                if self.motion.get() == Motion::Landing {
+                    self.motion.set(Motion::None);
+                    self.enter();
+                } else if self.motion.get() == Motion::Playing {
+                    if self.quitting.get() {
+                        self.exit();
+                    } else {
+                        self.settle((0.0, 0.0));
+                    }
+                } else {
+                    self.trigger(Trigger::AnimationEnd);
+                }
+            }
+        }
+    }
+}
+
+/// Current monotonic time in microseconds, the time base of the frame clock.
+fn now() -> i64 {
+    monotonic_time()
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use gio::glib::Variant;
+use gio::prelude::{ApplicationExt, ToVariant};
+use gio::{DBusConnection, DBusMethodInvocation, DBusNodeInfo};
+
+use super::buddy::Buddy;
+use super::server::execute;
+use crate::control::{Command, Event};
+use crate::error::BuddyError;
+
+/// Name of the interface buddy exports.
+const INTERFACE: &str = "hqnnqh.buddy.Buddy";
+
+/// Name of errors returned by failed method calls.
+const ERROR: &str = "hqnnqh.buddy.Error.Failed";
+
+const INTROSPECTION: &str = r#"<node>
+  <interface name="hqnnqh.buddy.Buddy">
+    <method name="PlayAnimation">
+      <arg name="animation" type="s" direction="in"/>
+    </method>
+    <method name="MoveTo">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="SetState">
+      <arg name="state" type="s" direction="in"/>
+    </method>
+    <method name="Say">
+      <arg name="text" type="s" direction="in"/>
+    </method>
+    <method name="Reload"/>
+    <method name="SetPaused">
+      <arg name="paused" type="b" direction="in"/>
+    </method>
+    <property name="State" type="s" access="read"/>
+    <property name="Animation" type="s" access="read">
+      <annotation name="org.freedesktop.DBus.Property.EmitsChangedSignal" value="false"/>
+    </property>
+    <property name="Position" type="(ii)" access="read">
+      <annotation name="org.freedesktop.DBus.Property.EmitsChangedSignal" value="false"/>
+    </property>
+    <property name="Paused" type="b" access="read"/>
+    <signal name="Clicked"/>
+    <signal name="Error">
+      <arg name="message" type="s"/>
+    </signal>
+  </interface>
+</node>"#;
+
+/// Export buddy on the session bus, at the object path of the `application`. Does nothing if the application is not connected to the bus. May fail and return [BuddyError].
+pub(super) fn export(application: &gtk4::Application, buddy: &Rc<Buddy>) -> Result<(), BuddyError> {
+    let (Some(connection), Some(path)) = (
+        application.dbus_connection(),
+        application.dbus_object_path(),
+    ) else {
+        return Ok(());
+    };
+
+    let interface = DBusNodeInfo::for_xml(INTROSPECTION)?
+        .lookup_interface(INTERFACE)
+        .expect("Interface is part of the introspection data.");
+
+    let buddy_clone = Rc::clone(buddy);
+    let buddy_clone_2 = Rc::clone(buddy);
+    connection
+        .register_object(&path, &interface)
+        .method_call(move |_, _, _, _, method, parameters, invocation| {
+            call(&buddy_clone, method, &parameters, invocation);
+        })
+        .property(move |_, _, _, _, property| get(&buddy_clone_2, property))
+        .build()?;
+
+    // announce what happens to buddy
+    buddy.subscribe(move |event| {
+        emit(&connection, &path, event);
+        true
+    });
+    Ok(())
+}
+
+/// Carry out the call of `method` with `parameters` and return its result.
+fn call(buddy: &Rc<Buddy>, method: &str, parameters: &Variant, invocation: DBusMethodInvocation) {
+    let Some(command) = command(method, parameters) else {
+        invocation.return_dbus_error(
+            "org.freedesktop.DBus.Error.InvalidArgs",
+            &format!("Invalid call of method: {}", method),
+        );
+        return;
+    };
+
+    match execute(buddy, command) {
+        Ok(()) => invocation.return_value(None),
+        Err(err) => invocation.return_dbus_error(ERROR, &err.to_string()),
+    }
+}
+
+/// [Command] corresponding to the call of `method` with `parameters`, if valid.
+fn command(method: &str, parameters: &Variant) -> Option<Command> {
+    Some(match method {
+        "PlayAnimation" => {
+            let (animation,) = parameters.get()?;
+            Command::Play { animation }
+        }
+        "MoveTo" => {
+            let (x, y) = parameters.get()?;
+            Command::MoveTo { x, y }
+        }
+        "SetState" => {
+            let (state,) = parameters.get()?;
+            Command::SetState { state }
+        }
+        "Say" => {
+            let (text,) = parameters.get()?;
+            Command::Say { text }
+        }
+        "Reload" => Command::Reload,
+        "SetPaused" => {
+            let (paused,) = parameters.get()?;
+            Command::SetPaused { paused }
+        }
+        _ => return None,
+    })
+}
+
+/// Current value of `property`.
+fn get(buddy: &Buddy, property: &str) -> Variant {
+    let status = buddy.status();
+
+    match property {
+        "State" => status.state.to_variant(),
+        "Animation" => status.animation.to_variant(),
+        "Position" => (status.x, status.y).to_variant(),
+        "Paused" => status.paused.to_variant(),
+        _ => ().to_variant(),
+    }
+}
+
+/// Emit the signal announcing `event`.
+fn emit(connection: &DBusConnection, path: &str, event: &Event) {
+    let (property, value) = match event {
+        Event::Clicked => {
+            let _ = connection.emit_signal(None, path, INTERFACE, "Clicked", None);
+            return;
+        }
+        Event::Error { message } => {
+            let _ = connection.emit_signal(
+                None,
+                path,
+                INTERFACE,
+                "Error",
+                Some(&(message,).to_variant()),
+            );
+            return;
+        }
+        // changes too often
+        Event::Moved { .. } => return,
+        Event::State { state } => ("State", state.to_variant()),
+        Event::Paused { paused } => ("Paused", paused.to_variant()),
+    };
+
+    let changed = HashMap::from([(property.to_string(), value)]);
+    let _ = connection.emit_signal(
+        None,
+        path,
+        "org.freedesktop.DBus.Properties",
+        "PropertiesChanged",
+        Some(&(INTERFACE, changed, Vec::<String>::new()).to_variant()),
+    );
+}
@@ -4,7 +4,7 @@ use gtk4::CssProvider;
 
 use gdk4::cairo::{RectangleInt, Region};
 use gdk4::prelude::{DisplayExt, MonitorExt, SurfaceExt};
-use gdk4::Display;
+use gdk4::{Display, Rectangle};
 
 /// Update click-able section of buddy on screen.
 pub(super) fn update_input_region(window: &ApplicationWindow, character_size: i32, x: i32, y: i32) {
@@ -12,12 +12,37 @@ pub(super) fn update_input_region(window: &ApplicationWindow, character_size: i3
     window.surface().unwrap().set_input_region(&region);
 }
 
+/// Keep buddy's bottom left corner at `x`, `y` (from the bottom) within the screen.
+pub(super) fn clamp_position(
+    x: i32,
+    y: i32,
+    character_size: i32,
+    screen_width: i32,
+    screen_height: i32,
+) -> (i32, i32) {
+    (
+        x.clamp(0, (screen_width - character_size).max(0)),
+        y.clamp(0, (screen_height - character_size).max(0)),
+    )
+}
+
 /// Returns the screen resolution (width, height). May fail and return None.
 pub(super) fn screen_resolution(window: &ApplicationWindow) -> Option<(i32, i32)> {
+    let geometry = screen_geometry(window)?;
+    Some((geometry.width(), geometry.height()))
+}
+
+/// Returns the geometry of the screen buddy is shown on among all screens. May fail and return None.
+pub(super) fn screen_geometry(window: &ApplicationWindow) -> Option<Rectangle> {
     let display = Display::default()?;
 
     let monitor = display.monitor_at_surface(&window.surface()?)?;
-    Some((monitor.geometry().width(), monitor.geometry().height()))
+    Some(monitor.geometry())
+}
+
+/// Convert `x`, `y` among all screens (from the top) to a position on the screen with `geometry` (from the bottom), as used for buddy.
+pub(super) fn to_screen(geometry: &Rectangle, x: i32, y: i32) -> (i32, i32) {
+    (x - geometry.x(), geometry.y() + geometry.height() - y)
 }
 
 /// Make buddy's background transparent.
@@ -0,0 +1,144 @@
+use std::env;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::Value;
+
+/// How long to wait for the compositor to answer.
+const TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Message type of the i3 IPC protocol (spoken by Sway) asking for the layout tree.
+const GET_TREE: u32 = 4;
+
+/// Rectangle of a window among all screens: x and y of its top left corner, width and height.
+pub(super) type Rect = (i32, i32, i32, i32);
+
+/// Windows shown on the screen whose top left corner lies at `origin` among all screens, or on any screen with Sway. None if the compositor cannot be asked (only Hyprland and Sway can).
+pub(super) fn windows(origin: (i32, i32)) -> Option<Vec<Rect>> {
+    match hyprland_socket() {
+        Some(socket) => hyprland_windows(&socket, origin),
+        None => sway_windows(),
+    }
+}
+
+/// Whether the compositor can be asked where windows are, i.e. whether Hyprland or Sway is running.
+pub(super) fn available() -> bool {
+    hyprland_socket().is_some() || env::var_os("SWAYSOCK").is_some()
+}
+
+/// Socket of the running Hyprland instance, if any.
+pub(super) fn hyprland_socket() -> Option<PathBuf> {
+    let signature = env::var("HYPRLAND_INSTANCE_SIGNATURE").ok()?;
+
+    // older versions of Hyprland keep their sockets in /tmp
+    env::var("XDG_RUNTIME_DIR")
+        .ok()
+        .map(|runtime_dir| PathBuf::from(runtime_dir).join("hypr"))
+        .into_iter()
+        .chain([PathBuf::from("/tmp/hypr")])
+        .map(|dir| dir.join(&signature).join(".socket.sock"))
+        .find(|socket| socket.exists())
+}
+
+/// Send `command` to Hyprland listening at `socket` and return its reply. May fail and return None.
+pub(super) fn hyprland(socket: &Path, command: &str) -> Option<String> {
+    let mut stream = UnixStream::connect(socket).ok()?;
+    stream.set_read_timeout(Some(TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(TIMEOUT)).ok()?;
+
+    stream.write_all(command.as_bytes()).ok()?;
+
+    // Hyprland closes the connection once it has replied
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).ok()?;
+    Some(reply)
+}
+
+/// Windows on the active workspace of the Hyprland monitor at `origin`.
+fn hyprland_windows(socket: &Path, origin: (i32, i32)) -> Option<Vec<Rect>> {
+    let monitors: Value = serde_json::from_str(&hyprland(socket, "j/monitors")?).ok()?;
+    let workspace = monitors.as_array()?.iter().find(|monitor| {
+        monitor["x"].as_i64() == Some(origin.0 as i64)
+            && monitor["y"].as_i64() == Some(origin.1 as i64)
+    })?["activeWorkspace"]["id"]
+        .as_i64()?;
+
+    let clients: Value = serde_json::from_str(&hyprland(socket, "j/clients")?).ok()?;
+    Some(
+        clients
+            .as_array()?
+            .iter()
+            .filter(|client| {
+                client["workspace"]["id"].as_i64() == Some(workspace)
+                    && client["mapped"].as_bool() != Some(false)
+                    && client["hidden"].as_bool() != Some(true)
+            })
+            .filter_map(|client| {
+                Some((
+                    client["at"][0].as_i64()? as i32,
+                    client["at"][1].as_i64()? as i32,
+                    client["size"][0].as_i64()? as i32,
+                    client["size"][1].as_i64()? as i32,
+                ))
+            })
+            .collect(),
+    )
+}
+
+/// Visible windows of Sway, including their title bars.
+fn sway_windows() -> Option<Vec<Rect>> {
+    let mut stream = UnixStream::connect(env::var("SWAYSOCK").ok()?).ok()?;
+    stream.set_read_timeout(Some(TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(TIMEOUT)).ok()?;
+
+    // magic string, payload length and message type
+    let mut message = b"i3-ipc".to_vec();
+    message.extend(0u32.to_ne_bytes());
+    message.extend(GET_TREE.to_ne_bytes());
+    stream.write_all(&message).ok()?;
+
+    let mut header = [0; 14];
+    stream.read_exact(&mut header).ok()?;
+    let length = u32::from_ne_bytes(header[6..10].try_into().ok()?) as usize;
+
+    let mut payload = vec![0; length];
+    stream.read_exact(&mut payload).ok()?;
+
+    let tree: Value = serde_json::from_slice(&payload).ok()?;
+    let mut windows = Vec::new();
+    collect_sway_windows(&tree, &mut windows);
+    Some(windows)
+}
+
+/// Add the visible windows among `node` and its children to `windows`.
+fn collect_sway_windows(node: &Value, windows: &mut Vec<Rect>) {
+    // only windows belong to a process
+    if node["pid"].is_i64() && node["visible"].as_bool() == Some(true) {
+        let rect = &node["rect"];
+        let title_bar = node["deco_rect"]["height"].as_i64().unwrap_or_default();
+
+        if let (Some(x), Some(y), Some(width), Some(height)) = (
+            rect["x"].as_i64(),
+            rect["y"].as_i64(),
+            rect["width"].as_i64(),
+            rect["height"].as_i64(),
+        ) {
+            windows.push((
+                x as i32,
+                (y - title_bar) as i32,
+                width as i32,
+                (height + title_bar) as i32,
+            ));
+        }
+    }
+
+    for child in ["nodes", "floating_nodes"]
+        .into_iter()
+        .filter_map(|children| node[children].as_array())
+        .flatten()
+    {
+        collect_sway_windows(child, windows);
+    }
+}
@@ -1,34 +1,39 @@
 use std::cell::Cell;
-use std::cell::RefCell;
-use std::path::Path;
 use std::rc::Rc;
-use std::sync::atomic::AtomicBool;
-use std::sync::atomic::Ordering;
-use std::sync::Arc;
-use std::time::Duration;
 
 use gio::prelude::{ApplicationExt, ApplicationExtManual};
-use glib::{timeout_add_local, ControlFlow};
-use gtk4::prelude::{GtkWindowExt, WidgetExt};
-use gtk4::{ApplicationWindow, GestureClick};
+use glib::{unix_signal_add_local, ControlFlow};
+use gtk4::prelude::{GestureDragExt, GtkWindowExt, WidgetExt};
+use gtk4::{Align, ApplicationWindow, GestureClick, GestureDrag};
 use gtk4_layer_shell::{Edge, Layer, LayerShell};
+use helpers::clamp_position;
 use helpers::load_css;
 use helpers::screen_resolution;
-use helpers::update_input_region;
-use rand::Rng;
-use sprite::preload_images;
-use state::State;
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM, SIGUSR1, SIGUSR2};
+use sprite::{preload_images, IDLE};
+use state::StateMachine;
 
-use crate::config::Config;
+use buddy::Buddy;
+
+use crate::config::{Config, SignalAction};
+use crate::control::APP_ID_PREFIX;
 use crate::error::BuddyError;
 
+mod buddy;
+mod dbus;
 mod helpers;
+mod ipc;
+mod movement;
+mod persist;
+mod physics;
+mod pointer;
+mod server;
 mod sprite;
 mod state;
 
 /// Prepare and render character.
 pub(crate) fn render_character(config: Config, sprites_path: String) {
-    let app_id = format!("hqnnqh.buddy.instance{}", std::process::id());
+    let app_id = format!("{}{}", APP_ID_PREFIX, std::process::id());
 
     let application = gtk4::Application::new(Some(app_id.as_str()), Default::default());
 
@@ -45,6 +50,8 @@ pub(crate) fn render_character(config: Config, sprites_path: String) {
         }
     });
     application.run_with_args::<&str>(&[]);
+
+    server::close();
 }
 
 /// Active GTK app. May fail and return [BuddyError].
@@ -53,28 +60,11 @@ fn activate(
     config: &Config,
     sprites_path: &Rc<String>,
 ) -> Result<(), BuddyError> {
-    // used to handle signal to reload sprites
-    let reload_sprites = Arc::new(AtomicBool::new(false));
-
-    signal_hook::flag::register(signal_hook::consts::SIGUSR1, Arc::clone(&reload_sprites))
-        .map_err(BuddyError::from)?;
-
-    signal_hook::flag::register(signal_hook::consts::SIGUSR2, Arc::clone(&reload_sprites))
-        .map_err(BuddyError::from)?;
-
     let Config {
         character_size,
-        fps,
-        movement_speed,
-        onclick_event_chance,
         x,
         y,
-        left,
-        flip_horizontal,
-        flip_vertical,
         debug,
-        signal_frequency,
-        automatic_reload,
         ..
     } = *config;
 
@@ -85,13 +75,9 @@ fn activate(
     // Display above normal windows
     window.set_layer(Layer::Overlay);
 
-    for (anchor, state) in [
-        (Edge::Left, true),
-        (Edge::Right, true),
-        (Edge::Top, false),
-        (Edge::Bottom, true),
-    ] {
-        window.set_anchor(anchor, state);
+    // cover the whole screen to be able to move buddy anywhere, input is restricted to buddy
+    for anchor in [Edge::Left, Edge::Right, Edge::Top, Edge::Bottom] {
+        window.set_anchor(anchor, true);
     }
 
     window.present(); // present prematurely to be able to get screen resolution
@@ -117,136 +103,165 @@ fn activate(
 
     let character_size = character_size as i32;
 
-    let sprites = Rc::new(RefCell::new(preload_images(
-        Path::new(sprites_path.as_str()),
-        flip_horizontal,
-        flip_vertical,
-    )?));
+    let sprites = preload_images(sprites_path, config)?;
 
-    // start with idle sprites
-    let character = Rc::new(gtk4::Image::from_paintable(Some(
-        &sprites.as_ref().borrow().0[0],
-    )));
+    if debug {
+        let info = &sprites.info;
+        println!(
+            "Sprite pack: {} by {} ({})",
+            info.name.as_deref().unwrap_or("unnamed"),
+            info.author.as_deref().unwrap_or("unknown author"),
+            info.license.as_deref().unwrap_or("no license"),
+        );
+    }
 
-    let state = Rc::new(Cell::new(State::Idle));
-    character.set_pixel_size(character_size);
+    let mut machine = StateMachine::for_config(config, &sprites)?;
 
-    // default position
-    character.set_margin_start(x);
-    character.set_margin_bottom(y);
+    // start with idle sprites
+    // sprites are scaled when loading them, show them as they are to keep pixel art crisp
+    let character = gtk4::Picture::new();
+    character.set_paintable(Some(sprites.get(IDLE).frame(0, false)));
+    character.set_can_shrink(false);
+    character.set_halign(Align::Start);
+    character.set_valign(Align::End);
+
+    // default position, placing the anchor point of the sprites at the starting coordinates
+    let (anchor_x, anchor_y) = sprites.info.anchor;
+    let x = x - (anchor_x * character_size as f64) as i32;
+    let y = y - ((1.0 - anchor_y) * character_size as f64) as i32;
+
+    // resume where buddy was when it was last closed, states the behaviour no longer has are skipped
+    let saved = config.restore_state.then(persist::load).flatten();
+    let (x, y) = match &saved {
+        Some(saved) => {
+            let _ = machine.set(&saved.state);
+            (
+                saved.x.clamp(0, (screen_width - character_size).max(0)),
+                saved.y.min(screen_height - character_size),
+            )
+        }
+        None => (x, y),
+    };
 
-    window.set_child(Some(&*character));
-    window.set_default_size(character_size, character_size);
+    window.set_child(Some(&character));
     window.set_resizable(false);
 
-    // default input region
-    update_input_region(&window, character_size, x, 0);
-
-    let sprites_clone = Rc::clone(&sprites);
-    let sprites_path_clone = Rc::clone(sprites_path);
-
-    timeout_add_local(
-        Duration::from_millis(1000 / signal_frequency as u64),
-        move || {
-            if automatic_reload || reload_sprites.swap(false, Ordering::Relaxed) {
-                match preload_images(
-                    Path::new(sprites_path_clone.as_str()),
-                    flip_horizontal,
-                    flip_vertical,
-                ) {
-                    Ok(sprites) => *sprites_clone.borrow_mut() = sprites,
-                    Err(err) => println!("Warning: Could not update sprites: {}", err),
-                }
-            }
-            ControlFlow::from(true)
-        },
+    let buddy = Buddy::new(
+        window,
+        character,
+        sprites,
+        machine,
+        sprites_path.to_string(),
+        config,
+        (screen_width, screen_height),
     );
 
-    let character_clone = Rc::clone(&character);
-    let state_clone = Rc::clone(&state);
+    if (config.follow_cursor || config.flee_cursor) && !buddy.pointer.tracked() {
+        println!("Warning: The compositor does not tell where the pointer is, buddy only notices it while it is over buddy.");
+    }
 
-    let mut frame = 0;
+    if let Some(saved) = saved {
+        buddy.left.set(saved.left);
+    }
 
-    // animate character
-    timeout_add_local(Duration::from_millis(1000 / fps as u64), move || {
-        match (*state_clone).get() {
-            State::Idle => {
-                frame = (frame + 1) % sprites.as_ref().borrow().0.len();
-                character_clone.set_paintable(Some(&sprites.as_ref().borrow().0[frame]));
-            }
-            State::InitiatingClick => {
-                frame = 0;
-                state_clone.set(State::Click);
-            }
-            State::Click => {
-                if frame == sprites.as_ref().borrow().2.len() {
-                    state_clone.set(State::Idle);
-                    frame = 0;
-                } else {
-                    character_clone.set_paintable(Some(&sprites.as_ref().borrow().2[frame]));
-
-                    frame += 1;
-                }
+    // default position and input region
+    buddy.move_to(x, y);
+
+    for signal in [SIGUSR1, SIGUSR2, SIGHUP] {
+        let buddy_clone = Rc::clone(&buddy);
+        unix_signal_add_local(signal, move || {
+            // looked up on every signal to follow configuration reloads
+            match buddy_clone.signal_action(signal) {
+                SignalAction::ReloadSprites => buddy_clone.request_reload(),
+                SignalAction::ReloadConfig => buddy_clone.reload_config(),
+                SignalAction::TogglePause => buddy_clone.set_paused(!buddy_clone.paused()),
+                SignalAction::Quit => buddy_clone.quit(),
+                SignalAction::Ignore => {}
             }
-            // Running
-            State::Running | State::InitiatingRun => {
-                frame = (frame + 1) % sprites.as_ref().borrow().1.len();
+            ControlFlow::Continue
+        });
+    }
 
-                character_clone.set_paintable(Some(&sprites.as_ref().borrow().1[frame]));
+    // say goodbye instead of being killed
+    for signal in [SIGTERM, SIGINT] {
+        let buddy_clone = Rc::clone(&buddy);
+        unix_signal_add_local(signal, move || {
+            buddy_clone.quit();
+            ControlFlow::Continue
+        });
+    }
 
-                if state_clone.get() == State::InitiatingRun {
-                    state_clone.set(State::Running)
-                }
-            }
-        }
-        ControlFlow::from(true)
-    });
+    // accept commands of scripts and status bars
+    if let Err(err) = server::listen(&buddy) {
+        println!("Warning: {}", err);
+    }
+    if let Err(err) = dbus::export(application, &buddy) {
+        println!("Warning: Could not export buddy on D-Bus: {}", err);
+    }
 
-    let character_clone = Rc::clone(&character);
-    let state_clone = Rc::clone(&state);
-
-    // move character
-    timeout_add_local(
-        Duration::from_millis(1000 / movement_speed as u64),
-        move || {
-            if state_clone.get() == State::Running {
-                // update position
-                let value = if left {
-                    let new_position = character_clone.margin_start() - 10;
-                    if new_position <= -(character_size * 2) {
-                        (screen_width + 10) as f64
-                    } else {
-                        new_position as f64
-                    }
-                } else {
-                    (character_clone.margin_start() as f64 + 10.0) % (screen_width as f64 + 10.0)
-                };
-                // move along screen
-                character_clone.set_margin_start(value as i32);
-                update_input_region(&window, character_size, value as i32, 0);
-            }
-            ControlFlow::from(true)
-        },
-    );
+    // animate character, falling down first if started in the air
+    buddy.start();
 
-    // change state of character (idle/initiating run)
+    // change state of character, unless the click turned out to be a drag
     let gesture = GestureClick::new();
 
-    gesture.connect_pressed(
+    let buddy_clone = Rc::clone(&buddy);
+    gesture.connect_released(
         move |_gesture: &GestureClick, _n_press: i32, _x: f64, _y: f64| {
-            if state.get() != State::Click && state.get() != State::InitiatingClick {
-                // initiate click event
-                if state.get() == State::Idle
-                    && (rand::thread_rng().gen_range(0..100) + 1) as u8 <= onclick_event_chance
+            buddy_clone.click();
+        },
+    );
+
+    buddy.character.add_controller(gesture);
+
+    // drag and drop character
+    let gesture = GestureDrag::new();
+    // position of buddy when the drag began
+    let origin = Rc::new(Cell::new((0, 0)));
+
+    let buddy_clone = Rc::clone(&buddy);
+    let origin_clone = Rc::clone(&origin);
+    gesture.connect_drag_begin(move |_gesture: &GestureDrag, _x: f64, _y: f64| {
+        origin_clone.set(buddy_clone.position());
+    });
+
+    let buddy_clone = Rc::clone(&buddy);
+    gesture.connect_drag_update(
+        move |_gesture: &GestureDrag, offset_x: f64, offset_y: f64| {
+            let (offset_x, offset_y) = (offset_x as i32, offset_y as i32);
+
+            if !buddy_clone.dragged() {
+                // wait for the pointer to move far enough to tell a drag from a click
+                if !buddy_clone
+                    .window
+                    .drag_check_threshold(0, 0, offset_x, offset_y)
                 {
-                    state.set(State::InitiatingClick);
-                } else {
-                    state.set(!state.get());
+                    return;
+                }
+                if !buddy_clone.pick_up() {
+                    return;
                 }
             }
+
+            let (x, y) = origin.get();
+            let (x, y) = clamp_position(
+                x + offset_x,
+                y - offset_y,
+                character_size,
+                screen_width,
+                screen_height,
+            );
+            buddy_clone.drag_to(x, y);
         },
     );
 
-    character.add_controller(gesture);
+    let buddy_clone = Rc::clone(&buddy);
+    gesture.connect_drag_end(move |_gesture: &GestureDrag, _x: f64, _y: f64| {
+        if buddy_clone.dragged() {
+            buddy_clone.put_down();
+        }
+    });
+
+    buddy.window.add_controller(gesture);
     Ok(())
 }
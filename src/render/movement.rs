@@ -0,0 +1,213 @@
+use rand::Rng;
+
+use crate::config::EdgePolicy;
+
+/// Direction buddy is mostly moving into.
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum Heading {
+    Horizontal,
+    Up,
+    Down,
+}
+
+impl Heading {
+    /// Heading of a move from `from` to `to` (y from the bottom of the screen).
+    pub(super) fn new(from: (i32, i32), to: (i32, i32)) -> Self {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+
+        if dy.abs() <= dx.abs() {
+            Heading::Horizontal
+        } else if dy > 0 {
+            Heading::Up
+        } else {
+            Heading::Down
+        }
+    }
+}
+
+/// Area buddy's bottom left corner may move within (y from the bottom of the screen).
+pub(super) struct Area {
+    pub(super) left: i32,
+    pub(super) right: i32,
+    pub(super) ground: i32,
+    pub(super) ceiling: i32,
+}
+
+impl Area {
+    /// Random position within the area.
+    pub(super) fn random(&self) -> (i32, i32) {
+        let mut rng = rand::thread_rng();
+        (
+            rng.gen_range(self.left..=self.right.max(self.left)),
+            rng.gen_range(self.ground..=self.ceiling.max(self.ground)),
+        )
+    }
+}
+
+/// Top edge of a window buddy can stand on (x from the left, y from the bottom of the screen).
+#[derive(Clone, Copy)]
+pub(super) struct Ledge {
+    pub(super) left: i32,
+    pub(super) right: i32,
+    pub(super) top: i32,
+}
+
+/// Walk one step of `step` pixels along the ground. Returns the new position and whether the edge of the screen has been reached, according to `edge`:
+/// - wrapping around, buddy enters the screen again on the other side.
+/// - bouncing or stopping, buddy stays on the screen.
+/// - respawning, buddy walks off the screen.
+pub(super) fn walk(
+    (x, y): (i32, i32),
+    left: bool,
+    step: i32,
+    edge: EdgePolicy,
+    character_size: i32,
+    screen_width: i32,
+) -> ((i32, i32), bool) {
+    let next = if left { x - step } else { x + step };
+
+    match edge {
+        EdgePolicy::Wrap => {
+            let value = if left {
+                if next <= -(character_size * 2) {
+                    screen_width + step
+                } else {
+                    next
+                }
+            } else {
+                next % (screen_width + step)
+            };
+
+            ((value, y), (left && value > x) || (!left && value < x))
+        }
+        EdgePolicy::Bounce | EdgePolicy::Stop => {
+            let value = next.clamp(0, (screen_width - character_size).max(0));
+            ((value, y), value != next)
+        }
+        EdgePolicy::Respawn => ((next, y), next <= -character_size || next >= screen_width),
+    }
+}
+
+/// Move one step of `step` pixels straight towards `target`. Returns the new position and whether the target has been reached.
+pub(super) fn approach((x, y): (i32, i32), target: (i32, i32), step: i32) -> ((i32, i32), bool) {
+    let (dx, dy) = ((target.0 - x) as f64, (target.1 - y) as f64);
+    let distance = dx.hypot(dy);
+
+    if distance <= step as f64 {
+        return (target, true);
+    }
+
+    let factor = step as f64 / distance;
+    (
+        (
+            x + (dx * factor).round() as i32,
+            y + (dy * factor).round() as i32,
+        ),
+        false,
+    )
+}
+
+/// Walk one step of `step` pixels towards the side of the screen in the direction of `left`, then climb up along it. Returns the new position and whether the top has been reached.
+pub(super) fn climb((x, y): (i32, i32), left: bool, step: i32, area: &Area) -> ((i32, i32), bool) {
+    let wall = if left { area.left } else { area.right };
+
+    if x != wall {
+        let x = if left {
+            (x - step).max(wall)
+        } else {
+            (x + step).min(wall)
+        };
+        return ((x, y), false);
+    }
+
+    let next = (y + step).min(area.ceiling);
+    ((x, next), next != y && next == area.ceiling)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: i32 = 50;
+    const WIDTH: i32 = 800;
+
+    fn step(x: i32, left: bool, edge: EdgePolicy) -> (i32, bool) {
+        let ((x, y), reached) = walk((x, 20), left, 10, edge, SIZE, WIDTH);
+        assert_eq!(y, 20);
+        (x, reached)
+    }
+
+    #[test]
+    fn wraps_around_the_screen() {
+        assert_eq!(step(700, false, EdgePolicy::Wrap), (710, false));
+        assert_eq!(step(795, false, EdgePolicy::Wrap), (805, false));
+        assert_eq!(step(805, false, EdgePolicy::Wrap), (5, true));
+        assert_eq!(step(-85, true, EdgePolicy::Wrap), (-95, false));
+        assert_eq!(step(-95, true, EdgePolicy::Wrap), (810, true));
+    }
+
+    #[test]
+    fn stays_on_the_screen_when_bouncing_or_stopping() {
+        for edge in [EdgePolicy::Bounce, EdgePolicy::Stop] {
+            assert_eq!(step(700, false, edge), (710, false));
+            assert_eq!(step(740, false, edge), (750, false));
+            assert_eq!(step(745, false, edge), (750, true));
+            assert_eq!(step(750, false, edge), (750, true));
+            assert_eq!(step(10, true, edge), (0, false));
+            assert_eq!(step(5, true, edge), (0, true));
+        }
+    }
+
+    #[test]
+    fn walks_off_the_screen_when_respawning() {
+        assert_eq!(step(780, false, EdgePolicy::Respawn), (790, false));
+        assert_eq!(step(790, false, EdgePolicy::Respawn), (800, true));
+        assert_eq!(step(-30, true, EdgePolicy::Respawn), (-40, false));
+        assert_eq!(step(-40, true, EdgePolicy::Respawn), (-50, true));
+    }
+
+    #[test]
+    fn approaches_target() {
+        assert_eq!(approach((0, 0), (30, 40), 10), ((6, 8), false));
+        assert_eq!(approach((0, 0), (30, 40), 50), ((30, 40), true));
+        assert_eq!(approach((30, 40), (30, 40), 10), ((30, 40), true));
+    }
+
+    #[test]
+    fn climbs_up_the_side_of_the_screen() {
+        let area = Area {
+            left: 0,
+            right: 750,
+            ground: 0,
+            ceiling: 300,
+        };
+
+        assert_eq!(climb((700, 0), false, 100, &area), ((750, 0), false));
+        assert_eq!(climb((750, 0), false, 100, &area), ((750, 100), false));
+        assert_eq!(climb((750, 250), false, 100, &area), ((750, 300), true));
+        assert_eq!(climb((750, 300), false, 100, &area), ((750, 300), false));
+        assert_eq!(climb((50, 0), true, 100, &area), ((0, 0), false));
+    }
+
+    #[test]
+    fn picks_random_positions_within_the_area() {
+        let area = Area {
+            left: 10,
+            right: 20,
+            ground: 0,
+            ceiling: 5,
+        };
+
+        for _ in 0..100 {
+            let (x, y) = area.random();
+            assert!((10..=20).contains(&x) && (0..=5).contains(&y));
+        }
+    }
+
+    #[test]
+    fn tells_heading() {
+        assert!(Heading::new((0, 0), (10, 5)) == Heading::Horizontal);
+        assert!(Heading::new((0, 0), (5, 10)) == Heading::Up);
+        assert!(Heading::new((0, 0), (-5, -10)) == Heading::Down);
+    }
+}
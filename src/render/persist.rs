@@ -0,0 +1,47 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::error::BuddyError;
+
+/// Where buddy was and what it was doing when it was closed.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct SavedState {
+    /// Position of buddy's bottom left corner (from the bottom of the screen).
+    pub(super) x: i32,
+    pub(super) y: i32,
+    /// Direction buddy moved into.
+    pub(super) left: bool,
+    /// State of the behaviour.
+    pub(super) state: String,
+}
+
+/// File the state is saved to: `$XDG_STATE_HOME/buddy/state.toml`, shared by all buddies.
+fn state_path() -> Option<PathBuf> {
+    env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+        .map(|dir| dir.join("buddy").join("state.toml"))
+}
+
+/// State saved when buddy was last closed, if any.
+pub(super) fn load() -> Option<SavedState> {
+    let content = fs::read_to_string(state_path()?).ok()?;
+    toml::from_str(&content).ok()
+}
+
+/// Save `state` to be restored on the next start. May fail and return [BuddyError].
+pub(super) fn save(state: &SavedState) -> Result<(), BuddyError> {
+    let path =
+        state_path().ok_or_else(|| BuddyError::SaveFailed("No home directory".to_string()))?;
+    let fail = |err: std::io::Error| BuddyError::SaveFailed(err.to_string());
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(fail)?;
+    }
+
+    let content = toml::to_string(state).map_err(|err| BuddyError::SaveFailed(err.to_string()))?;
+    fs::write(path, content).map_err(fail)
+}
@@ -0,0 +1,237 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::config::Physics;
+
+/// Slowest speed (pixels per second) buddy still bounces off the ground with, anything slower lands.
+const MIN_BOUNCE_SPEED: f64 = 100.0;
+
+/// How far back pointer positions are taken into account for the release velocity.
+const TRACKING_WINDOW: Duration = Duration::from_millis(100);
+
+/// Edges buddy's bottom left corner is kept within (y from the bottom of the screen).
+pub(super) struct Bounds {
+    pub(super) left: f64,
+    pub(super) right: f64,
+    pub(super) ground: f64,
+    pub(super) ceiling: f64,
+}
+
+/// Position (from the bottom of the screen) and velocity of buddy flying through the air.
+pub(super) struct Body {
+    pub(super) x: f64,
+    pub(super) y: f64,
+    /// Velocity in pixels per second, right and upwards positive.
+    pub(super) velocity: (f64, f64),
+}
+
+impl Body {
+    /// Body at `position` with `velocity`, limited to the terminal velocity.
+    pub(super) fn new(position: (i32, i32), velocity: (f64, f64), physics: &Physics) -> Self {
+        let limit = physics.terminal_velocity as f64;
+
+        Body {
+            x: position.0 as f64,
+            y: position.1 as f64,
+            velocity: (
+                velocity.0.clamp(-limit, limit),
+                velocity.1.clamp(-limit, limit),
+            ),
+        }
+    }
+
+    /// Advance the body by `delta` seconds, bouncing off the edges of `bounds` and sliding along the ground. Returns whether it has come to rest on the ground.
+    pub(super) fn step(&mut self, physics: &Physics, bounds: &Bounds, delta: f64) -> bool {
+        let restitution = physics.restitution.min(100) as f64 / 100.0;
+        let (mut velocity_x, mut velocity_y) = self.velocity;
+
+        velocity_y =
+            (velocity_y - physics.gravity as f64 * delta).max(-(physics.terminal_velocity as f64));
+
+        self.x += velocity_x * delta;
+        self.y += velocity_y * delta;
+
+        // side walls and ceiling
+        if self.x < bounds.left || self.x > bounds.right {
+            self.x = self.x.clamp(bounds.left, bounds.right);
+            velocity_x = -velocity_x * restitution;
+        }
+        if self.y > bounds.ceiling {
+            self.y = bounds.ceiling;
+            velocity_y = -velocity_y * restitution;
+        }
+
+        let grounded = self.y <= bounds.ground;
+
+        if grounded {
+            self.y = bounds.ground;
+            velocity_y = -velocity_y * restitution;
+
+            if velocity_y < MIN_BOUNCE_SPEED {
+                velocity_y = 0.0;
+
+                // slow down while sliding along the ground
+                let friction = physics.friction as f64 * delta;
+                velocity_x = velocity_x.signum() * (velocity_x.abs() - friction).max(0.0);
+            }
+        }
+
+        self.velocity = (velocity_x, velocity_y);
+
+        grounded && self.velocity == (0.0, 0.0)
+    }
+}
+
+/// Recent positions of the pointer, used to determine the velocity buddy is thrown with.
+#[derive(Default)]
+pub(super) struct Tracker {
+    samples: VecDeque<(Instant, (i32, i32))>,
+}
+
+impl Tracker {
+    pub(super) fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Record buddy's `position` while dragged.
+    pub(super) fn push(&mut self, position: (i32, i32)) {
+        let now = Instant::now();
+
+        self.samples.push_back((now, position));
+        while self
+            .samples
+            .front()
+            .is_some_and(|(time, _)| now - *time > TRACKING_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Velocity in pixels per second (right and upwards positive) over the recently recorded positions. Zero if the pointer has been held still.
+    pub(super) fn velocity(&self) -> (f64, f64) {
+        let now = Instant::now();
+        let mut recent = self
+            .samples
+            .iter()
+            .filter(|(time, _)| now - *time <= TRACKING_WINDOW);
+
+        match (recent.next(), recent.next_back()) {
+            (Some((start, from)), Some((end, to))) if end > start => {
+                let elapsed = (*end - *start).as_secs_f64();
+                (
+                    (to.0 - from.0) as f64 / elapsed,
+                    (to.1 - from.1) as f64 / elapsed,
+                )
+            }
+            _ => (0.0, 0.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread::sleep;
+
+    use super::*;
+
+    const PHYSICS: Physics = Physics {
+        gravity: 2000,
+        terminal_velocity: 1500,
+        restitution: 50,
+        friction: 1000,
+    };
+
+    const BOUNDS: Bounds = Bounds {
+        left: 0.0,
+        right: 1000.0,
+        ground: 0.0,
+        ceiling: 500.0,
+    };
+
+    #[test]
+    fn limits_velocity_to_terminal_velocity() {
+        let body = Body::new((0, 0), (5000.0, -5000.0), &PHYSICS);
+
+        assert_eq!(body.velocity, (1500.0, -1500.0));
+    }
+
+    #[test]
+    fn falls_with_gravity() {
+        let mut body = Body::new((100, 400), (0.0, 0.0), &PHYSICS);
+
+        assert!(!body.step(&PHYSICS, &BOUNDS, 0.1));
+        assert_eq!(body.velocity, (0.0, -200.0));
+        assert_eq!(body.y, 380.0);
+        assert_eq!(body.x, 100.0);
+    }
+
+    #[test]
+    fn bounces_off_the_ground() {
+        let mut body = Body::new((100, 10), (0.0, -1000.0), &PHYSICS);
+
+        assert!(!body.step(&PHYSICS, &BOUNDS, 0.1));
+        assert_eq!(body.y, BOUNDS.ground);
+        // half of the speed is kept
+        assert_eq!(body.velocity.1, 600.0);
+    }
+
+    #[test]
+    fn lands_once_too_slow_to_bounce() {
+        let mut body = Body::new((100, 0), (0.0, -50.0), &PHYSICS);
+
+        assert!(body.step(&PHYSICS, &BOUNDS, 0.01));
+        assert_eq!(body.y, BOUNDS.ground);
+        assert_eq!(body.velocity, (0.0, 0.0));
+    }
+
+    #[test]
+    fn slides_along_the_ground_until_friction_stops_it() {
+        let mut body = Body::new((100, 0), (150.0, 0.0), &PHYSICS);
+
+        assert!(!body.step(&PHYSICS, &BOUNDS, 0.01));
+        assert_eq!(body.velocity, (140.0, 0.0));
+
+        let steps = (0..100)
+            .position(|_| body.step(&PHYSICS, &BOUNDS, 0.01))
+            .unwrap();
+        assert_eq!(steps, 13);
+        assert_eq!(body.velocity, (0.0, 0.0));
+    }
+
+    #[test]
+    fn bounces_off_walls_and_ceiling() {
+        let mut body = Body::new((990, 490), (1000.0, 1000.0), &PHYSICS);
+
+        body.step(&PHYSICS, &BOUNDS, 0.1);
+        assert_eq!((body.x, body.y), (BOUNDS.right, BOUNDS.ceiling));
+        assert_eq!(body.velocity, (-500.0, -400.0));
+
+        let mut body = Body::new((10, 100), (-1000.0, 0.0), &PHYSICS);
+
+        body.step(&PHYSICS, &BOUNDS, 0.1);
+        assert_eq!(body.x, BOUNDS.left);
+        assert_eq!(body.velocity.0, 500.0);
+    }
+
+    #[test]
+    fn tracks_release_velocity() {
+        let mut tracker = Tracker::default();
+        assert_eq!(tracker.velocity(), (0.0, 0.0));
+
+        tracker.push((0, 0));
+        assert_eq!(tracker.velocity(), (0.0, 0.0));
+
+        sleep(Duration::from_millis(20));
+        tracker.push((10, -10));
+        let (x, y) = tracker.velocity();
+        assert!(x > 0.0 && y < 0.0);
+        assert_eq!(x, -y);
+
+        // held still for longer than the tracking window
+        sleep(TRACKING_WINDOW);
+        assert_eq!(tracker.velocity(), (0.0, 0.0));
+
+        tracker.clear();
+        assert_eq!(tracker.velocity(), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,97 @@
+use std::cell::Cell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use gtk4::prelude::WidgetExt;
+use gtk4::{ApplicationWindow, EventControllerMotion};
+
+use gdk4::Rectangle;
+
+use super::helpers::{screen_geometry, to_screen};
+use super::ipc::{hyprland, hyprland_socket};
+
+/// How long a queried pointer position is reused (in microseconds).
+const CACHE_DURATION: i64 = 50_000;
+
+/// Where the pointer position comes from.
+enum Source {
+    /// Asking Hyprland, which knows the position anywhere on the screen. Holds the socket and the geometry of the screen among all screens.
+    Hyprland(PathBuf, Rectangle),
+    /// Motion events of buddy's window, which only arrive while the pointer is over buddy as input is restricted to it. Holds the last position within the window.
+    Window(Rc<Cell<Option<(f64, f64)>>>),
+}
+
+/// Position of the mouse pointer, tracked beyond the click-able section of buddy if the compositor allows it.
+pub(super) struct Pointer {
+    source: Source,
+    window: ApplicationWindow,
+    /// When the position was last queried.
+    queried: Cell<Option<i64>>,
+    /// Last queried position.
+    last: Cell<Option<(i32, i32)>>,
+}
+
+impl Pointer {
+    /// Track the pointer on the screen of `window`.
+    pub(super) fn new(window: &ApplicationWindow) -> Self {
+        let source = match hyprland_socket() {
+            Some(socket) => Source::Hyprland(
+                socket,
+                screen_geometry(window).unwrap_or_else(|| Rectangle::new(0, 0, 0, 0)),
+            ),
+            None => {
+                let position = Rc::new(Cell::new(None));
+                let controller = EventControllerMotion::new();
+
+                let position_clone = Rc::clone(&position);
+                controller.connect_motion(move |_controller, x, y| {
+                    position_clone.set(Some((x, y)));
+                });
+                window.add_controller(controller);
+
+                Source::Window(position)
+            }
+        };
+
+        Pointer {
+            source,
+            window: window.clone(),
+            queried: Cell::new(None),
+            last: Cell::new(None),
+        }
+    }
+
+    /// Whether the pointer is tracked anywhere on the screen, rather than only while over buddy.
+    pub(super) fn tracked(&self) -> bool {
+        matches!(self.source, Source::Hyprland(..))
+    }
+
+    /// Position of the pointer at `now` (x from the left, y from the bottom of the screen), if known and on buddy's screen.
+    pub(super) fn position(&self, now: i64) -> Option<(i32, i32)> {
+        if self
+            .queried
+            .get()
+            .is_some_and(|time| now - time < CACHE_DURATION)
+        {
+            return self.last.get();
+        }
+
+        let (width, height) = (self.window.width(), self.window.height());
+        let position = match &self.source {
+            Source::Hyprland(socket, geometry) => hyprland(socket, "cursorpos").and_then(|reply| {
+                let (x, y) = reply.trim().split_once(',')?;
+                Some(to_screen(
+                    geometry,
+                    x.trim().parse().ok()?,
+                    y.trim().parse().ok()?,
+                ))
+            }),
+            Source::Window(position) => position.get().map(|(x, y)| (x as i32, height - y as i32)),
+        }
+        .filter(|(x, y)| (0..width).contains(x) && (0..height).contains(y));
+
+        self.queried.set(Some(now));
+        self.last.set(position);
+        position
+    }
+}
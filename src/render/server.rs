@@ -0,0 +1,168 @@
+use std::fs;
+use std::io::{ErrorKind, Read, Write};
+use std::net::Shutdown;
+use std::os::fd::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::rc::Rc;
+
+use glib::{idle_add_local_once, unix_fd_add_local, ControlFlow, IOCondition};
+
+use super::buddy::Buddy;
+use crate::control::{socket_path, Command, Reply};
+use crate::error::BuddyError;
+
+/// Longest line accepted from a client (in bytes). Commands are far shorter, clients sending longer lines are disconnected.
+const MAX_LINE: usize = 8192;
+
+/// Listen for [Command]s on the control socket of this process. May fail and return [BuddyError].
+pub(super) fn listen(buddy: &Rc<Buddy>) -> Result<(), BuddyError> {
+    let path = socket_path(std::process::id())?;
+    let fail = |err| BuddyError::ControlSocketFailed(path.display().to_string(), err);
+
+    // left behind by an earlier process with the same id
+    let _ = fs::remove_file(&path);
+
+    let listener = UnixListener::bind(&path).map_err(fail)?;
+    listener.set_nonblocking(true).map_err(fail)?;
+
+    let buddy = Rc::clone(buddy);
+    unix_fd_add_local(listener.as_raw_fd(), IOCondition::IN, move |_, _| {
+        while let Ok((stream, _)) = listener.accept() {
+            serve(&buddy, stream);
+        }
+        ControlFlow::Continue
+    });
+    Ok(())
+}
+
+/// Remove the control socket of this process.
+pub(super) fn close() {
+    if let Ok(path) = socket_path(std::process::id()) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Answer the commands arriving on `stream`, one per line, until the client hangs up.
+fn serve(buddy: &Rc<Buddy>, mut stream: UnixStream) {
+    if stream.set_nonblocking(true).is_err() {
+        return;
+    }
+
+    let buddy = Rc::clone(buddy);
+    let mut buffer = Vec::new();
+
+    unix_fd_add_local(
+        stream.as_raw_fd(),
+        IOCondition::IN | IOCondition::HUP | IOCondition::ERR,
+        move |_, _| {
+            let mut chunk = [0; 4096];
+            let closed = loop {
+                match stream.read(&mut chunk) {
+                    Ok(0) => break true,
+                    Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => break false,
+                    Err(_) => break true,
+                }
+
+                while let Some(end) = buffer.iter().position(|byte| *byte == b'\n') {
+                    let line: Vec<u8> = buffer.drain(..=end).collect();
+                    respond(&buddy, &mut stream, &line);
+                }
+
+                // no command is that long, don't buffer whatever the client sends
+                if buffer.len() > MAX_LINE {
+                    let _ = stream.shutdown(Shutdown::Both);
+                    return ControlFlow::Break;
+                }
+            };
+
+            if closed {
+                // last command without a line break
+                if !buffer.trim_ascii().is_empty() {
+                    respond(&buddy, &mut stream, &buffer);
+                }
+                return ControlFlow::Break;
+            }
+            ControlFlow::Continue
+        },
+    );
+}
+
+/// Execute the command in `line`, if any, and write the reply to `stream`.
+fn respond(buddy: &Rc<Buddy>, stream: &mut UnixStream, line: &[u8]) {
+    if line.trim_ascii().is_empty() {
+        return;
+    }
+
+    let command = serde_json::from_slice(line);
+    let subscribe = matches!(command, Ok(Command::Subscribe));
+
+    let result = command
+        .map_err(|err| format!("Invalid command: {}", err))
+        .and_then(|command| execute(buddy, command).map_err(|err| err.to_string()));
+
+    let reply = match result {
+        Ok(()) => Reply {
+            ok: true,
+            error: None,
+            status: Some(buddy.status()),
+        },
+        Err(error) => Reply {
+            ok: false,
+            error: Some(error),
+            status: Some(buddy.status()),
+        },
+    };
+
+    if let Ok(mut reply) = serde_json::to_vec(&reply) {
+        reply.push(b'\n');
+        let _ = stream.write_all(&reply);
+    }
+
+    if subscribe {
+        forward(buddy, stream);
+    }
+}
+
+/// Write every event of `buddy` to `stream`, one JSON object per line, until the client hangs up or falls behind.
+fn forward(buddy: &Buddy, stream: &UnixStream) {
+    // outlives the connection being read from, as the client may stop sending
+    let Ok(stream) = stream.try_clone() else {
+        return;
+    };
+
+    buddy.subscribe(move |event| {
+        let sent = serde_json::to_vec(event).is_ok_and(|mut line| {
+            line.push(b'\n');
+            (&stream).write_all(&line).is_ok()
+        });
+
+        // hang up rather than leaving the client with a truncated line, closing the connection being read from as well
+        if !sent {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        sent
+    });
+}
+
+/// Carry out `command`. May fail and return [BuddyError].
+pub(super) fn execute(buddy: &Rc<Buddy>, command: Command) -> Result<(), BuddyError> {
+    match command {
+        Command::Status => {}
+        Command::Play { animation } => buddy.play_animation(&animation)?,
+        Command::MoveTo { x, y } => buddy.place(x, y),
+        Command::SetState { state } => buddy.set_state(&state)?,
+        Command::SetSpeed { speed } => buddy.set_speed(speed),
+        Command::Say { text } => buddy.say(&text),
+        Command::SetPaused { paused } => buddy.set_paused(paused),
+        Command::Reload => buddy.request_reload(),
+        // handled by the connection
+        Command::Subscribe => {}
+        Command::Quit => {
+            // reply before quitting
+            let buddy = Rc::clone(buddy);
+            idle_add_local_once(move || buddy.quit());
+        }
+    }
+    Ok(())
+}
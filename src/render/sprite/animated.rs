@@ -0,0 +1,87 @@
+use std::borrow::Cow;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use gdk4::gdk_pixbuf::{Colorspace, Pixbuf};
+use gdk4::glib::Bytes;
+use image::codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder};
+use image::{AnimationDecoder, Frames};
+
+use crate::config::Config;
+use crate::error::BuddyError;
+
+use super::{load_frame, pack::Pack, Frame};
+
+/// File extensions of animated images, in the order they are looked up.
+const EXTENSIONS: [&str; 4] = ["gif", "webp", "apng", "png"];
+
+/// Animated image that replaces the directory of `animation`, e.g. `idle.gif`. Static PNG files are skipped.
+pub(super) fn find(pack: &Pack, animation: &str) -> Option<PathBuf> {
+    EXTENSIONS
+        .iter()
+        .map(|extension| PathBuf::from(format!("{}.{}", animation, extension)))
+        .find(|path| pack.is_file(path) && is_animated(pack, path))
+}
+
+/// Whether the image at `path` is an animated GIF, APNG or WebP file.
+pub(super) fn is_animated(pack: &Pack, path: &Path) -> bool {
+    match extension(path).as_deref() {
+        Some("gif" | "webp" | "apng") => true,
+        Some("png") => open(pack, path)
+            .ok()
+            .and_then(|reader| PngDecoder::new(reader).ok())
+            .and_then(|decoder| decoder.is_apng().ok())
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Decode every frame of an animated image together with its delay. May fail and return [BuddyError].
+pub(super) fn load(pack: &Pack, path: &Path, config: &Config) -> Result<Vec<Frame>, BuddyError> {
+    let reader = open(pack, path)?;
+
+    let frames: Frames = match extension(path).as_deref() {
+        Some("gif") => GifDecoder::new(reader)?.into_frames(),
+        Some("webp") => WebPDecoder::new(reader)?.into_frames(),
+        _ => PngDecoder::new(reader)?.apng()?.into_frames(),
+    };
+
+    frames
+        .map(|frame| {
+            let frame = frame?;
+
+            let (numerator, denominator) = frame.delay().numer_denom_ms();
+            // treat missing delays like an unspecified duration
+            let duration = (numerator > 0)
+                .then(|| Duration::from_millis((numerator / denominator.max(1)) as u64));
+
+            let buffer = frame.into_buffer();
+            let (width, height) = (buffer.width() as i32, buffer.height() as i32);
+            let pixbuf = Pixbuf::from_bytes(
+                &Bytes::from_owned(buffer.into_raw()),
+                Colorspace::Rgb,
+                true,
+                8,
+                width,
+                height,
+                width * 4,
+            );
+
+            Ok(Frame {
+                duration,
+                ..load_frame(pixbuf, config)?
+            })
+        })
+        .collect()
+}
+
+fn open(pack: &Pack, path: &Path) -> Result<Cursor<Cow<'static, [u8]>>, BuddyError> {
+    pack.read(path).map(Cursor::new)
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase())
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use serde_derive::Deserialize;
+
+use crate::config::behaviour::Behaviour;
+use crate::config::Config;
+use crate::error::BuddyError;
+
+use super::pack::Pack;
+use super::sheet::SheetLayout;
+use super::{
+    discover, load_default, load_defaults, load_directory, load_file, Animation, Facing, Frame,
+    PackInfo, Sprites, LAND,
+};
+
+/// File name of the optional sprite pack manifest.
+pub(super) const MANIFEST: &str = "sprite.toml";
+
+/// Sprite pack manifest.
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    name: Option<String>,
+    author: Option<String>,
+    license: Option<String>,
+    /// Native frame size (width, height) in pixels.
+    size: Option<(i32, i32)>,
+    /// Point of a frame that is placed at buddy's position, as fractions of the frame size.
+    anchor: Option<(f64, f64)>,
+    /// Direction the sprites are drawn facing.
+    facing: Option<Facing>,
+    /// Sprite sheet that animations may be sliced from.
+    sheet: Option<SheetLayout>,
+    #[serde(default)]
+    animations: HashMap<String, ManifestAnimation>,
+    /// Behaviour designed for the sprite pack.
+    behaviour: Option<Behaviour>,
+}
+
+/// Declaration of a single animation.
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "AnimationFields")]
+struct ManifestAnimation {
+    /// Falls back to the default location of the animation if None.
+    source: Option<Source>,
+    fps: Option<u32>,
+    /// Durations of the individual frames in milliseconds, in playback order.
+    durations: Vec<u64>,
+    playback: Option<Playback>,
+}
+
+/// Fields of an animation declaration as written in the manifest, before checking that they describe a single source.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnimationFields {
+    files: Option<Vec<String>>,
+    directory: Option<String>,
+    row: Option<i32>,
+    count: Option<i32>,
+    fps: Option<u32>,
+    #[serde(default)]
+    durations: Vec<u64>,
+    playback: Option<Playback>,
+}
+
+/// Where the frames of an animation are taken from.
+#[derive(Debug)]
+enum Source {
+    /// Frame files in playback order, relative to the sprites directory. Animated images contribute all of their frames.
+    Files { files: Vec<String> },
+    /// Directory with frame files, sorted by file name.
+    Directory { directory: String },
+    /// Frames on the sprite sheet of the manifest.
+    Sheet { row: i32, count: i32 },
+}
+
+impl TryFrom<AnimationFields> for ManifestAnimation {
+    type Error = String;
+
+    fn try_from(fields: AnimationFields) -> Result<Self, Self::Error> {
+        let source = match (fields.files, fields.directory, fields.row, fields.count) {
+            (None, None, None, None) => None,
+            (Some(files), None, None, None) => Some(Source::Files { files }),
+            (None, Some(directory), None, None) => Some(Source::Directory { directory }),
+            (None, None, Some(row), Some(count)) => Some(Source::Sheet { row, count }),
+            (None, None, _, _) => {
+                return Err("`row` and `count` must be given together".to_string())
+            }
+            _ => {
+                return Err(
+                    "only one of `files`, `directory` or `row` and `count` may be given"
+                        .to_string(),
+                )
+            }
+        };
+
+        Ok(ManifestAnimation {
+            source,
+            fps: fields.fps,
+            durations: fields.durations,
+            playback: fields.playback,
+        })
+    }
+}
+
+/// Whether an animation repeats or ends after its last frame.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Playback {
+    Loop,
+    Once,
+}
+
+/// Load the sprite pack described by the manifest of `pack`. Animations that are not declared are loaded from their default location. May fail and return [BuddyError].
+pub(super) fn load(pack: &Pack, config: &Config) -> Result<Sprites, BuddyError> {
+    let manifest = pack.read_to_string(Path::new(MANIFEST))?;
+    let manifest: Manifest = toml::from_str(&manifest)?;
+
+    let sheet = manifest
+        .sheet
+        .as_ref()
+        .map(|sheet| sheet.open(pack, manifest.size))
+        .transpose()?;
+
+    let load = |name: &str, declaration: ManifestAnimation| -> Result<Animation, BuddyError> {
+        // buddy only carries on once it has landed
+        if name == LAND && declaration.playback == Some(Playback::Loop) {
+            return Err(BuddyError::MustPlayOnce(name.to_string()));
+        }
+
+        let frames = match declaration.source {
+            None => load_default(pack, name, config)?,
+            Some(Source::Files { files }) => files
+                .into_iter()
+                .map(|file| load_file(pack, Path::new(&file), config))
+                .collect::<Result<Vec<Vec<Frame>>, BuddyError>>()?
+                .into_iter()
+                .flatten()
+                .collect(),
+            Some(Source::Directory { directory }) => {
+                load_directory(pack, Path::new(&directory), config)?
+            }
+            Some(Source::Sheet { row, count }) => sheet
+                .as_ref()
+                .ok_or_else(|| BuddyError::MissingSheet(name.to_string()))?
+                .slice(name, row, count, config)?,
+        };
+
+        let mut animation = Animation::new(name, frames);
+        animation.fps = declaration.fps;
+        // declared durations take precedence over delays of animated images
+        for (frame, duration) in animation.frames.iter_mut().zip(declaration.durations) {
+            frame.duration = Some(Duration::from_millis(duration));
+        }
+        if let Some(playback) = declaration.playback {
+            animation.looping = playback == Playback::Loop;
+        }
+        Ok(animation)
+    };
+
+    // undeclared animations found in the sprite pack
+    let mut animations = load_defaults(
+        pack,
+        discover(pack)
+            .into_iter()
+            .filter(|name| !manifest.animations.contains_key(name)),
+        config,
+    )?;
+
+    for (name, declaration) in manifest.animations {
+        let animation = load(&name, declaration)?;
+        animations.insert(name, animation);
+    }
+
+    Ok(Sprites {
+        animations,
+        info: PackInfo {
+            name: manifest.name,
+            author: manifest.author,
+            license: manifest.license,
+            anchor: manifest.anchor.unwrap_or(PackInfo::default().anchor),
+            facing: manifest.facing.unwrap_or_default(),
+            behaviour: manifest.behaviour,
+        },
+    })
+}
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::vec::Vec;
+
+use gdk4::gdk_pixbuf::{InterpType, Pixbuf};
+use gdk4::Texture;
+use serde_derive::Deserialize;
+
+use crate::config::behaviour::Behaviour;
+use crate::config::{Config, ScalingFilter};
+use crate::error::BuddyError;
+
+use pack::Pack;
+
+mod animated;
+mod manifest;
+mod pack;
+mod sheet;
+
+/// Animation sprites, by animation name.
+pub(super) struct Sprites {
+    pub(super) animations: HashMap<String, Animation>,
+    pub(super) info: PackInfo,
+}
+
+impl Sprites {
+    /// Animation called `name`, falling back to idle if the sprite pack does not provide it.
+    pub(super) fn get(&self, name: &str) -> &Animation {
+        self.animations
+            .get(name)
+            .unwrap_or_else(|| &self.animations[IDLE])
+    }
+
+    /// Whether the sprite pack provides the animation called `name`.
+    pub(super) fn has(&self, name: &str) -> bool {
+        self.animations.contains_key(name)
+    }
+}
+
+/// Frames of a single animation and how to play them.
+pub(super) struct Animation {
+    pub(super) frames: Vec<Frame>,
+    /// Frames per second of frames without their own duration. Falls back to the configured fps if None.
+    pub(super) fps: Option<u32>,
+    /// Whether the animation starts over after its last frame.
+    pub(super) looping: bool,
+}
+
+/// Single frame of an animation.
+pub(super) struct Frame {
+    pub(super) texture: Texture,
+    /// Horizontally mirrored texture, shown while buddy moves into the opposite direction.
+    pub(super) mirrored: Texture,
+    /// How long the frame is shown. Falls back to the frame rate of the animation if None.
+    pub(super) duration: Option<Duration>,
+}
+
+/// Metadata of a sprite pack.
+pub(super) struct PackInfo {
+    pub(super) name: Option<String>,
+    pub(super) author: Option<String>,
+    pub(super) license: Option<String>,
+    /// Point of a frame (fractions of its width and height, from the top left corner) that is placed at buddy's position.
+    pub(super) anchor: (f64, f64),
+    /// Direction the sprites are drawn facing.
+    pub(super) facing: Facing,
+    /// Behaviour designed for the sprite pack.
+    pub(super) behaviour: Option<Behaviour>,
+}
+
+/// Horizontal direction sprites face.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum Facing {
+    Left,
+    #[default]
+    Right,
+}
+
+impl Default for PackInfo {
+    fn default() -> Self {
+        PackInfo {
+            name: None,
+            author: None,
+            license: None,
+            // bottom left corner
+            anchor: (0.0, 1.0),
+            facing: Facing::Right,
+            behaviour: None,
+        }
+    }
+}
+
+impl Animation {
+    /// Create animation with default playback: click and land are played once, everything else loops.
+    fn new(name: &str, frames: Vec<Frame>) -> Self {
+        Animation {
+            frames,
+            fps: None,
+            looping: name != CLICK && name != LAND,
+        }
+    }
+
+    /// Texture at index `frame`, clamped to the last frame. Mirrored horizontally if `mirrored`.
+    pub(super) fn frame(&self, frame: usize, mirrored: bool) -> &Texture {
+        let frame = &self.frames[frame.min(self.frames.len() - 1)];
+
+        if mirrored {
+            &frame.mirrored
+        } else {
+            &frame.texture
+        }
+    }
+
+    /// How long the frame at index `frame` is shown, using `fps` if neither the frame nor the animation specify it.
+    pub(super) fn duration(&self, frame: usize, fps: u32) -> Duration {
+        self.frames[frame.min(self.frames.len() - 1)]
+            .duration
+            .unwrap_or_else(|| Duration::from_millis(1000 / self.fps.unwrap_or(fps).max(1) as u64))
+    }
+
+    /// Index of the frame following `frame`. None if the animation does not loop and has finished.
+    pub(super) fn next_frame(&self, frame: usize) -> Option<usize> {
+        if frame + 1 < self.frames.len() {
+            Some(frame + 1)
+        } else if self.looping {
+            Some(0)
+        } else {
+            None
+        }
+    }
+}
+
+pub(super) const IDLE: &str = "idle";
+pub(super) const RUN: &str = "run";
+pub(super) const CLICK: &str = "click";
+/// Optional animation played while buddy is dragged.
+pub(super) const DRAG: &str = "drag";
+/// Optional animation played while buddy falls down.
+pub(super) const FALL: &str = "fall";
+/// Optional animation played once when buddy hits the ground.
+pub(super) const LAND: &str = "land";
+/// Optional animation played while moving upwards.
+pub(super) const UP: &str = "up";
+/// Optional animation played while moving downwards.
+pub(super) const DOWN: &str = "down";
+/// Optional animation played once before buddy closes.
+pub(super) const GOODBYE: &str = "goodbye";
+
+/// Animations every sprite pack must provide.
+const REQUIRED: [&str; 3] = [IDLE, RUN, CLICK];
+
+/// (Pre-)load the images for better preformance. Frames are scaled to the configured character size. May fail and return [BuddyError].
+///
+/// The sprites path is either a directory or the name of a built-in sprite pack (e.g. `builtin:chicken`). Sprites are described by a sprite pack manifest or a sprite sheet descriptor, if present. Otherwise they are read from one subdirectory or animated image (GIF, APNG, WebP) per animation, named after the animation. Besides the required idle, run and click animations, packs may provide any number of additional animations.
+pub(super) fn preload_images(sprites_path: &str, config: &Config) -> Result<Sprites, BuddyError> {
+    let pack = Pack::new(sprites_path)?;
+
+    let mut sprites = if pack.is_file(Path::new(manifest::MANIFEST)) {
+        manifest::load(&pack, config)?
+    } else if pack.is_file(Path::new(sheet::DESCRIPTOR)) {
+        sheet::load(&pack, config)?
+    } else {
+        Sprites {
+            animations: load_defaults(&pack, discover(&pack), config)?,
+            info: PackInfo::default(),
+        }
+    };
+
+    // animations without frames (e.g. empty directories) count as missing
+    sprites
+        .animations
+        .retain(|_, animation| !animation.frames.is_empty());
+
+    match REQUIRED
+        .into_iter()
+        .find(|animation| !sprites.has(animation))
+    {
+        Some(animation) => Err(BuddyError::MissingAnimation(
+            animation.to_string(),
+            sprites_path.to_string(),
+        )),
+        None => Ok(sprites),
+    }
+}
+
+/// Names of the animations found in the root of `pack`: subdirectories with images and animated images. Hidden files and directories are skipped. Always includes the required animations.
+fn discover(pack: &Pack) -> Vec<String> {
+    let root = Path::new("");
+
+    let mut animations: Vec<String> = pack
+        .dirs(root)
+        .into_iter()
+        .filter(|dir| has_images(pack, dir))
+        .chain(
+            pack.files(root)
+                .into_iter()
+                .filter(|file| animated::is_animated(pack, file)),
+        )
+        .filter(|path| {
+            !path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with('.'))
+        })
+        .filter_map(|path| {
+            path.file_stem()
+                .and_then(|name| name.to_str())
+                .map(str::to_string)
+        })
+        .chain(REQUIRED.map(str::to_string))
+        .collect();
+
+    animations.sort();
+    animations.dedup();
+    animations
+}
+
+/// Whether the directory at `path` contains any image gdk-pixbuf can decode, judging by the file extensions.
+fn has_images(pack: &Pack, path: &Path) -> bool {
+    let extensions: Vec<String> = Pixbuf::formats()
+        .iter()
+        .flat_map(|format| format.extensions())
+        .map(|extension| extension.to_lowercase())
+        .collect();
+
+    pack.files(path).iter().any(|file| {
+        file.extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| extensions.contains(&extension.to_lowercase()))
+    })
+}
+
+/// Load every animation in `animations` from its default location, see [load_default].
+fn load_defaults(
+    pack: &Pack,
+    animations: impl IntoIterator<Item = String>,
+    config: &Config,
+) -> Result<HashMap<String, Animation>, BuddyError> {
+    animations
+        .into_iter()
+        .map(|name| {
+            let frames = load_default(pack, &name, config)?;
+            Ok((name.clone(), Animation::new(&name, frames)))
+        })
+        .collect()
+}
+
+/// Load the frames of `animation` from its subdirectory or, if there is none, from an animated image of the same name.
+fn load_default(pack: &Pack, animation: &str, config: &Config) -> Result<Vec<Frame>, BuddyError> {
+    let animation_path = PathBuf::from(animation);
+
+    match animated::find(pack, animation) {
+        Some(path) if !pack.is_dir(&animation_path) => animated::load(pack, &path, config),
+        _ => load_directory(pack, &animation_path, config),
+    }
+}
+
+/// Load the frames of a single animation from a directory. Frames are sorted by file name. A missing directory yields no frames.
+fn load_directory(
+    pack: &Pack,
+    animation_path: &Path,
+    config: &Config,
+) -> Result<Vec<Frame>, BuddyError> {
+    pack.files(animation_path)
+        .into_iter()
+        .map(|file_path| load_file(pack, &file_path, config))
+        .collect::<Result<Vec<Vec<Frame>>, BuddyError>>()
+        .map(|frames| frames.into_iter().flatten().collect())
+}
+
+/// Load a single image. Animated images yield all of their frames. May fail and return [BuddyError].
+fn load_file(pack: &Pack, file_path: &Path, config: &Config) -> Result<Vec<Frame>, BuddyError> {
+    if animated::is_animated(pack, file_path) {
+        animated::load(pack, file_path, config)
+    } else {
+        Ok(vec![load_frame(pack.pixbuf(file_path)?, config)?])
+    }
+}
+
+/// Turn a single frame into a texture and its mirror image, scaling it to fit the character size and flipping it as requested. May fail and return [BuddyError].
+fn load_frame(mut pixbuf: Pixbuf, config: &Config) -> Result<Frame, BuddyError> {
+    let size = config.character_size as f64;
+    let mut factor = size / pixbuf.width().max(pixbuf.height()).max(1) as f64;
+
+    if config.integer_scaling {
+        factor = if factor >= 1.0 {
+            factor.floor()
+        } else {
+            1.0 / (1.0 / factor).ceil()
+        };
+    }
+
+    if factor != 1.0 {
+        let interpolation = match config.scaling_filter {
+            ScalingFilter::Nearest => InterpType::Nearest,
+            ScalingFilter::Bilinear => InterpType::Bilinear,
+        };
+
+        pixbuf = pixbuf
+            .scale_simple(
+                ((pixbuf.width() as f64 * factor).round() as i32).max(1),
+                ((pixbuf.height() as f64 * factor).round() as i32).max(1),
+                interpolation,
+            )
+            .ok_or(BuddyError::ScalingFailed(config.character_size))?;
+    }
+
+    if config.flip_horizontal {
+        pixbuf = pixbuf.flip(true).ok_or(BuddyError::FlipFailed(true))?;
+    }
+
+    if config.flip_vertical {
+        pixbuf = pixbuf.flip(false).ok_or(BuddyError::FlipFailed(false))?;
+    }
+
+    let mirrored = pixbuf.flip(true).ok_or(BuddyError::FlipFailed(true))?;
+
+    Ok(Frame {
+        texture: Texture::for_pixbuf(&pixbuf),
+        mirrored: Texture::for_pixbuf(&mirrored),
+        duration: None,
+    })
+}
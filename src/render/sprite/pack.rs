@@ -0,0 +1,155 @@
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use gdk4::gdk_pixbuf::Pixbuf;
+use gdk4::glib::Bytes;
+use gio::{Cancellable, MemoryInputStream};
+use include_dir::{include_dir, Dir};
+
+use crate::config::BUILTIN_PREFIX;
+use crate::error::BuddyError;
+
+/// Sprite packs compiled into the binary.
+static BUILTIN: [(&str, Dir); 3] = [
+    (
+        "chicken",
+        include_dir!("$CARGO_MANIFEST_DIR/res/chicken_sprites"),
+    ),
+    (
+        "green_slime",
+        include_dir!("$CARGO_MANIFEST_DIR/res/green_slime_sprites"),
+    ),
+    (
+        "pikachu",
+        include_dir!("$CARGO_MANIFEST_DIR/res/pikachu_sprites"),
+    ),
+];
+
+/// Location a sprite pack is read from. All paths are relative to the root of the pack.
+pub(super) enum Pack {
+    /// Directory on disk.
+    Directory(PathBuf),
+    /// Sprite pack compiled into the binary.
+    Builtin(&'static str, &'static Dir<'static>),
+}
+
+impl Pack {
+    /// Resolve a sprites path to a directory or, if prefixed with [BUILTIN_PREFIX], a built-in sprite pack. May fail and return [BuddyError].
+    pub(super) fn new(sprites_path: &str) -> Result<Self, BuddyError> {
+        match sprites_path.strip_prefix(BUILTIN_PREFIX) {
+            Some(name) => BUILTIN
+                .iter()
+                .find(|(builtin, _)| *builtin == name)
+                .map(|(name, dir)| Pack::Builtin(name, dir))
+                .ok_or_else(|| BuddyError::UnknownPack(name.to_string())),
+            None => Ok(Pack::Directory(PathBuf::from(sprites_path))),
+        }
+    }
+
+    pub(super) fn is_file(&self, path: &Path) -> bool {
+        match self {
+            Pack::Directory(root) => root.join(path).is_file(),
+            Pack::Builtin(_, dir) => dir.get_file(path).is_some(),
+        }
+    }
+
+    pub(super) fn is_dir(&self, path: &Path) -> bool {
+        match self {
+            Pack::Directory(root) => root.join(path).is_dir(),
+            Pack::Builtin(_, dir) => dir.get_dir(path).is_some(),
+        }
+    }
+
+    /// Directories directly inside the directory at `path`, sorted by name. A missing directory yields no directories.
+    pub(super) fn dirs(&self, path: &Path) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = match self {
+            Pack::Directory(root) => std::fs::read_dir(root.join(path))
+                .map(|entry| {
+                    entry
+                        .filter_map(|dir| dir.ok())
+                        .filter(|dir| {
+                            dir.metadata()
+                                .ok()
+                                .is_some_and(|metadata| metadata.is_dir())
+                        })
+                        .map(|dir| path.join(dir.file_name()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            Pack::Builtin(_, dir) => Some(*dir)
+                .filter(|_| path.as_os_str().is_empty())
+                .or_else(|| dir.get_dir(path))
+                .map(|dir| dir.dirs().map(|dir| dir.path().to_path_buf()).collect())
+                .unwrap_or_default(),
+        };
+        dirs.sort();
+        dirs
+    }
+
+    /// Files directly inside the directory at `path`, sorted by file name. A missing directory yields no files.
+    pub(super) fn files(&self, path: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = match self {
+            Pack::Directory(root) => std::fs::read_dir(root.join(path))
+                .map(|entry| {
+                    entry
+                        .filter_map(|file| file.ok())
+                        .filter(|file| {
+                            file.metadata()
+                                .ok()
+                                .is_some_and(|metadata| metadata.is_file())
+                        })
+                        .map(|file| path.join(file.file_name()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            Pack::Builtin(_, dir) => Some(*dir)
+                .filter(|_| path.as_os_str().is_empty())
+                .or_else(|| dir.get_dir(path))
+                .map(|dir| dir.files().map(|file| file.path().to_path_buf()).collect())
+                .unwrap_or_default(),
+        };
+        files.sort();
+        files
+    }
+
+    /// Contents of the file at `path`. May fail and return [BuddyError].
+    pub(super) fn read(&self, path: &Path) -> Result<Cow<'static, [u8]>, BuddyError> {
+        match self {
+            Pack::Directory(root) => std::fs::read(root.join(path)).ok().map(Cow::Owned),
+            Pack::Builtin(_, dir) => dir
+                .get_file(path)
+                .map(|file| Cow::Borrowed(file.contents())),
+        }
+        .ok_or_else(|| BuddyError::SpritesCannotBeFound(self.display(path)))
+    }
+
+    /// Contents of the text file at `path`. May fail and return [BuddyError].
+    pub(super) fn read_to_string(&self, path: &Path) -> Result<String, BuddyError> {
+        String::from_utf8(self.read(path)?.into_owned())
+            .map_err(|_| BuddyError::SpritesCannotBeFound(self.display(path)))
+    }
+
+    /// Decode the image at `path`. May fail and return [BuddyError].
+    pub(super) fn pixbuf(&self, path: &Path) -> Result<Pixbuf, BuddyError> {
+        match self {
+            Pack::Directory(root) => Pixbuf::from_file(root.join(path)).map_err(BuddyError::from),
+            Pack::Builtin(..) => {
+                let bytes = match self.read(path)? {
+                    Cow::Borrowed(contents) => Bytes::from_static(contents),
+                    Cow::Owned(contents) => Bytes::from_owned(contents),
+                };
+
+                Pixbuf::from_stream(&MemoryInputStream::from_bytes(&bytes), None::<&Cancellable>)
+                    .map_err(BuddyError::from)
+            }
+        }
+    }
+
+    /// Human readable location of `path`, used in error messages.
+    pub(super) fn display(&self, path: &Path) -> String {
+        match self {
+            Pack::Directory(root) => root.join(path).to_string_lossy().to_string(),
+            Pack::Builtin(name, _) => format!("{}{}/{}", BUILTIN_PREFIX, name, path.display()),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use gdk4::gdk_pixbuf::Pixbuf;
+use serde_derive::Deserialize;
+
+use crate::config::Config;
+use crate::error::BuddyError;
+
+use super::{load_frame, pack::Pack, Animation, Frame, Sprites};
+
+/// File name of the descriptor that turns a sprites directory into a sprite sheet.
+pub(super) const DESCRIPTOR: &str = "sheet.toml";
+
+/// Layout of a single sprite sheet image.
+#[derive(Debug, Deserialize)]
+pub(super) struct SheetLayout {
+    /// Path of the sheet image, relative to the sprites directory.
+    pub(super) image: String,
+    pub(super) frame_width: Option<i32>,
+    pub(super) frame_height: Option<i32>,
+}
+
+/// Sprite sheet descriptor.
+#[derive(Debug, Deserialize)]
+struct Sheet {
+    #[serde(flatten)]
+    layout: SheetLayout,
+    animations: HashMap<String, SheetAnimation>,
+}
+
+/// Position of an animation on the sprite sheet.
+#[derive(Debug, Deserialize)]
+struct SheetAnimation {
+    row: i32,
+    count: i32,
+}
+
+/// Sprite sheet image together with its resolved frame size.
+pub(super) struct OpenSheet {
+    image: Pixbuf,
+    frame_width: i32,
+    frame_height: i32,
+}
+
+impl SheetLayout {
+    /// Load the sheet image. Frame dimensions that are not specified fall back to `native_size`. May fail and return [BuddyError].
+    pub(super) fn open(
+        &self,
+        pack: &Pack,
+        native_size: Option<(i32, i32)>,
+    ) -> Result<OpenSheet, BuddyError> {
+        let (frame_width, frame_height) =
+            self.frame_width
+                .zip(self.frame_height)
+                .or(native_size)
+                .ok_or_else(|| BuddyError::MissingFrameSize(self.image.clone()))?;
+
+        if frame_width <= 0 || frame_height <= 0 {
+            return Err(BuddyError::InvalidFrameSize(
+                self.image.clone(),
+                frame_width,
+                frame_height,
+            ));
+        }
+
+        Ok(OpenSheet {
+            image: pack.pixbuf(Path::new(&self.image))?,
+            frame_width,
+            frame_height,
+        })
+    }
+}
+
+impl OpenSheet {
+    /// Cut `count` frames starting at the first column of `row` out of the sheet. Animations with more frames than columns continue on the next row. May fail and return [BuddyError].
+    pub(super) fn slice(
+        &self,
+        animation: &str,
+        row: i32,
+        count: i32,
+        config: &Config,
+    ) -> Result<Vec<Frame>, BuddyError> {
+        let columns = self.image.width() / self.frame_width.max(1);
+
+        (0..count)
+            .map(|frame| {
+                let x = (frame % columns.max(1)) * self.frame_width;
+                let y = (row + frame / columns.max(1)) * self.frame_height;
+
+                if columns < 1
+                    || self.frame_height < 1
+                    || y < 0
+                    || y + self.frame_height > self.image.height()
+                {
+                    return Err(BuddyError::FrameOutOfBounds(animation.to_string(), frame));
+                }
+
+                load_frame(
+                    self.image
+                        .new_subpixbuf(x, y, self.frame_width, self.frame_height),
+                    config,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Slice the sprite sheet described by the sheet descriptor of `pack` into animation sprites. May fail and return [BuddyError].
+pub(super) fn load(pack: &Pack, config: &Config) -> Result<Sprites, BuddyError> {
+    let descriptor = pack.read_to_string(Path::new(DESCRIPTOR))?;
+    let sheet: Sheet = toml::from_str(&descriptor)?;
+
+    let image = sheet.layout.open(pack, None)?;
+
+    Ok(Sprites {
+        animations: sheet
+            .animations
+            .iter()
+            .map(|(name, SheetAnimation { row, count })| {
+                let frames = image.slice(name, *row, *count, config)?;
+
+                Ok((name.clone(), Animation::new(name, frames)))
+            })
+            .collect::<Result<_, BuddyError>>()?,
+        info: Default::default(),
+    })
+}
@@ -1,27 +1,303 @@
-use std::ops::Not;
+use std::collections::BTreeMap;
 
-/// State that buddy can be in at any time.
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub(crate) enum State {
-    Idle,
+use rand::Rng;
 
-    // special state for initiating run to ensure proper timing
-    InitiatingRun,
-    Running,
+use super::sprite::Sprites;
+use crate::config::behaviour::{Behaviour, StateDefinition, Target, Transition, Trigger};
+use crate::config::Config;
+use crate::error::BuddyError;
 
-    InitiatingClick,
-    Click,
+/// State machine driving buddy's behaviour.
+pub(crate) struct StateMachine {
+    behaviour: Behaviour,
+    current: String,
 }
 
-impl Not for State {
-    type Output = State;
+impl StateMachine {
+    /// Start in the initial state of `behaviour`. Fails with [BuddyError] if the behaviour refers to states that do not exist, has weighted targets without any weight or timers without a delay.
+    pub(crate) fn new(behaviour: Behaviour) -> Result<Self, BuddyError> {
+        if let Some(state) = std::iter::once(&behaviour.initial)
+            .chain(
+                behaviour
+                    .states
+                    .values()
+                    .flat_map(|state| state.transitions.iter())
+                    .flat_map(|transition| transition.target.states()),
+            )
+            .find(|state| !behaviour.states.contains_key(*state))
+        {
+            return Err(BuddyError::UnknownState(state.clone()));
+        }
+
+        for (name, state) in &behaviour.states {
+            for transition in &state.transitions {
+                if let Target::Weighted(states) = &transition.target {
+                    if Self::total(states).is_none_or(|total| total == 0) {
+                        return Err(BuddyError::InvalidWeights(name.clone()));
+                    }
+                }
 
-    fn not(self) -> Self::Output {
-        match self {
-            State::Running | State::InitiatingRun | State::InitiatingClick | State::Click => {
-                State::Idle
+                // a timer firing right away would re-enter timer states forever
+                if transition.trigger == Trigger::Timer
+                    && transition.after.unwrap_or_default() == 0
+                    && transition.until.unwrap_or_default() == 0
+                {
+                    return Err(BuddyError::MissingDelay(name.clone()));
+                }
             }
-            State::Idle => State::InitiatingRun,
         }
+
+        Ok(StateMachine {
+            current: behaviour.initial.clone(),
+            behaviour,
+        })
+    }
+
+    /// Machine for the behaviour of `config`, falling back to the one of the sprite pack and the default behaviour. Fails with [BuddyError] if the behaviour refers to states that do not exist.
+    pub(crate) fn for_config(config: &Config, sprites: &Sprites) -> Result<Self, BuddyError> {
+        // behaviour of config takes precedence over the one of the sprite pack
+        Self::new(
+            config
+                .behaviour
+                .clone()
+                .or_else(|| sprites.info.behaviour.clone())
+                .unwrap_or_else(|| Behaviour::new(config)),
+        )
+    }
+
+    /// Return to the initial state.
+    pub(crate) fn reset(&mut self) {
+        self.current = self.behaviour.initial.clone();
+    }
+
+    /// Change to `state`. Fails with [BuddyError] if the behaviour has no such state.
+    pub(crate) fn set(&mut self, state: &str) -> Result<(), BuddyError> {
+        if !self.behaviour.states.contains_key(state) {
+            return Err(BuddyError::UnknownState(state.to_string()));
+        }
+        self.current = state.to_string();
+        Ok(())
+    }
+
+    /// Name of the current state.
+    pub(crate) fn current(&self) -> &str {
+        &self.current
+    }
+
+    /// Definition of the current state.
+    pub(crate) fn state(&self) -> &StateDefinition {
+        &self.behaviour.states[&self.current]
+    }
+
+    /// Whether every state the current state may change to on `trigger` keeps buddy on the ground.
+    pub(crate) fn grounded_targets(&self, trigger: Trigger) -> bool {
+        self.state()
+            .transitions
+            .iter()
+            .filter(|transition| transition.trigger == trigger)
+            .flat_map(|transition| transition.target.states())
+            .all(|state| self.behaviour.states[state].movement.grounded())
+    }
+
+    /// Take the first transition of the current state that matches `trigger` and succeeds its chance. Returns whether a transition was taken.
+    pub(crate) fn trigger(&mut self, trigger: Trigger) -> bool {
+        let target = self
+            .state()
+            .transitions
+            .iter()
+            .filter(|transition| transition.trigger == trigger)
+            .find(|transition| Self::roll(transition))
+            .map(|transition| Self::pick(&transition.target));
+
+        target.map(|target| self.current = target).is_some()
+    }
+
+    /// Take the transition at `index` of the current state if it succeeds its chance. Returns whether it was taken.
+    pub(crate) fn take(&mut self, index: usize) -> bool {
+        let target = self
+            .state()
+            .transitions
+            .get(index)
+            .filter(|transition| Self::roll(transition))
+            .map(|transition| Self::pick(&transition.target));
+
+        target.map(|target| self.current = target).is_some()
+    }
+
+    fn roll(transition: &Transition) -> bool {
+        (rand::thread_rng().gen_range(0..100) + 1) as u8 <= transition.chance
+    }
+
+    /// Sum of the weights of weighted targets. None if it overflows.
+    fn total(states: &BTreeMap<String, u32>) -> Option<u32> {
+        states
+            .values()
+            .try_fold(0u32, |total, weight| total.checked_add(*weight))
+    }
+
+    /// Name of the state to change to. Weighted targets are picked at random, states without weight never.
+    fn pick(target: &Target) -> String {
+        match target {
+            Target::State(state) => state.clone(),
+            Target::Weighted(states) => {
+                let total = Self::total(states).unwrap_or(u32::MAX);
+                let mut roll = rand::thread_rng().gen_range(0..total.max(1));
+
+                states
+                    .iter()
+                    .find(|(_, weight)| {
+                        let hit = roll < **weight;
+                        roll = roll.saturating_sub(**weight);
+                        hit
+                    })
+                    .or_else(|| states.iter().next())
+                    .map(|(state, _)| state.clone())
+                    .unwrap_or_default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn machine(behaviour: &str) -> Result<StateMachine, BuddyError> {
+        StateMachine::new(toml::from_str(behaviour).unwrap())
+    }
+
+    #[test]
+    fn starts_in_initial_state() {
+        let machine = machine(
+            r#"
+            initial = "idle"
+            states.idle = { animation = "idle", transitions = [{ trigger = "click", target = "run" }] }
+            states.run = { animation = "run" }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(machine.current(), "idle");
+    }
+
+    #[test]
+    fn rejects_unknown_states() {
+        assert!(matches!(
+            machine(
+                r#"
+                initial = "sleep"
+                states.idle = { animation = "idle" }
+                "#,
+            ),
+            Err(BuddyError::UnknownState(state)) if state == "sleep"
+        ));
+        assert!(matches!(
+            machine(
+                r#"
+                initial = "idle"
+                states.idle = { animation = "idle", transitions = [{ trigger = "click", target = { run = 1 } }] }
+                "#,
+            ),
+            Err(BuddyError::UnknownState(state)) if state == "run"
+        ));
+    }
+
+    #[test]
+    fn rejects_weighted_targets_without_weight() {
+        for target in ["{}", "{ idle = 0 }", "{ idle = 4294967295, run = 1 }"] {
+            let behaviour = format!(
+                r#"
+                initial = "idle"
+                states.idle = {{ animation = "idle", transitions = [{{ trigger = "click", target = {} }}] }}
+                states.run = {{ animation = "run" }}
+                "#,
+                target
+            );
+
+            assert!(
+                matches!(machine(&behaviour), Err(BuddyError::InvalidWeights(state)) if state == "idle"),
+                "{}",
+                target
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_timers_without_delay() {
+        for timer in ["", ", after = 0", ", after = 0, until = 0"] {
+            let behaviour = format!(
+                r#"
+                initial = "idle"
+                states.idle = {{ animation = "idle", transitions = [{{ trigger = "timer", target = "idle"{} }}] }}
+                "#,
+                timer
+            );
+
+            assert!(
+                matches!(machine(&behaviour), Err(BuddyError::MissingDelay(state)) if state == "idle"),
+                "{}",
+                timer
+            );
+        }
+
+        assert!(machine(
+            r#"
+            initial = "idle"
+            states.idle = { animation = "idle", transitions = [{ trigger = "timer", target = "idle", until = 500 }] }
+            "#,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn picks_only_weighted_states() {
+        let target = Target::Weighted(BTreeMap::from([
+            ("idle".to_string(), 0),
+            ("run".to_string(), 1),
+            ("turn".to_string(), 0),
+        ]));
+
+        for _ in 0..100 {
+            assert_eq!(StateMachine::pick(&target), "run");
+        }
+        assert_eq!(
+            StateMachine::pick(&Target::State("idle".to_string())),
+            "idle"
+        );
+    }
+
+    #[test]
+    fn tells_whether_targets_stay_on_the_ground() {
+        let machine = machine(
+            r#"
+            initial = "idle"
+            states.idle = { animation = "idle", transitions = [{ trigger = "pointer_away", target = "follow" }, { trigger = "click", target = { follow = 1, chase = 1 } }] }
+            states.follow = { animation = "run", moving = true, movement = "follow" }
+            states.chase = { animation = "run", moving = true, movement = "chase" }
+            "#,
+        )
+        .unwrap();
+
+        assert!(machine.grounded_targets(Trigger::PointerAway));
+        assert!(!machine.grounded_targets(Trigger::Click));
+        assert!(machine.grounded_targets(Trigger::Edge));
+    }
+
+    #[test]
+    fn triggers_transitions() {
+        let mut machine = machine(
+            r#"
+            initial = "idle"
+            states.idle = { animation = "idle", transitions = [{ trigger = "click", target = "run", chance = 0 }, { trigger = "click", target = "run" }] }
+            states.run = { animation = "run" }
+            "#,
+        )
+        .unwrap();
+
+        assert!(!machine.trigger(Trigger::Edge));
+        assert_eq!(machine.current(), "idle");
+        assert!(machine.trigger(Trigger::Click));
+        assert_eq!(machine.current(), "run");
+        assert!(!machine.take(0));
     }
 }